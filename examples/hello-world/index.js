@@ -1,4 +1,4 @@
 #!/usr/bin/env node
 const hello = require('./node-api');
 
-console.log(hello.foo)
+console.log(hello.hello())
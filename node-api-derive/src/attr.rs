@@ -0,0 +1,189 @@
+use syn;
+use syn::{Attribute, LitStr, Path};
+
+/// Options given with `#[napi(...)]` on a struct or enum.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub rename_all: Option<RenameRule>,
+    pub tag: Option<String>,
+    pub untagged: bool,
+    pub numeric: bool,
+}
+
+/// Options given with `#[napi(...)]` on an enum variant.
+#[derive(Default)]
+pub struct VariantAttrs {
+    pub rename: Option<String>,
+    pub rename_all: Option<RenameRule>,
+}
+
+/// Options given with `#[napi(...)]` on a field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub default: Option<Default>,
+    pub flatten: bool,
+}
+
+pub enum Default {
+    Trait,
+    Path(Path),
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+        let mut result = ContainerAttrs::default();
+        for attr in napi_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    result.rename_all = Some(RenameRule::parse(&rule)?);
+                } else if meta.path.is_ident("tag") {
+                    let tag: LitStr = meta.value()?.parse()?;
+                    result.tag = Some(tag.value());
+                } else if meta.path.is_ident("untagged") {
+                    result.untagged = true;
+                } else if meta.path.is_ident("numeric") {
+                    result.numeric = true;
+                } else {
+                    return Err(meta.error("unsupported #[napi] attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+impl VariantAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<VariantAttrs> {
+        let mut result = VariantAttrs::default();
+        for attr in napi_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    result.rename = Some(name.value());
+                } else if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    result.rename_all = Some(RenameRule::parse(&rule)?);
+                } else {
+                    return Err(meta.error("unsupported #[napi] attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+        let mut result = FieldAttrs::default();
+        for attr in napi_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    result.rename = Some(name.value());
+                } else if meta.path.is_ident("skip") {
+                    result.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    result.flatten = true;
+                } else if meta.path.is_ident("default") {
+                    result.default = Some(if meta.input.peek(syn::Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        Default::Path(path.parse()?)
+                    } else {
+                        Default::Trait
+                    });
+                } else {
+                    return Err(meta.error("unsupported #[napi] attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+fn napi_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("napi")).collect()
+}
+
+/// The case conversion selected with `rename_all`.
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+}
+
+impl RenameRule {
+    fn parse(rule: &LitStr) -> syn::Result<RenameRule> {
+        match rule.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lower),
+            "UPPERCASE" => Ok(RenameRule::Upper),
+            "PascalCase" => Ok(RenameRule::Pascal),
+            "camelCase" => Ok(RenameRule::Camel),
+            "snake_case" => Ok(RenameRule::Snake),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnake),
+            "kebab-case" => Ok(RenameRule::Kebab),
+            _ => {
+                Err(syn::Error::new(rule.span(),
+                                    "unknown rename rule, expected one of \"lowercase\", \
+                                     \"UPPERCASE\", \"PascalCase\", \"camelCase\", \
+                                     \"snake_case\", \"SCREAMING_SNAKE_CASE\", \"kebab-case\""))
+            }
+        }
+    }
+
+    /// Applies the rule to a `snake_case` field name.
+    pub fn apply_to_field(&self, field: &str) -> String {
+        let words: Vec<&str> = field.split('_').filter(|word| !word.is_empty()).collect();
+        self.join(&words)
+    }
+
+    /// Applies the rule to a `PascalCase` variant name.
+    pub fn apply_to_variant(&self, variant: &str) -> String {
+        let mut words = Vec::new();
+        let mut start = 0;
+        for (index, c) in variant.char_indices().skip(1) {
+            if c.is_uppercase() {
+                words.push(&variant[start..index]);
+                start = index;
+            }
+        }
+        words.push(&variant[start..]);
+        self.join(&words)
+    }
+
+    fn join(&self, words: &[&str]) -> String {
+        let lower: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+        match *self {
+            RenameRule::Lower => lower.concat(),
+            RenameRule::Upper => lower.concat().to_uppercase(),
+            RenameRule::Pascal => lower.iter().map(|word| capitalize(word)).collect(),
+            RenameRule::Camel => {
+                lower.iter()
+                    .enumerate()
+                    .map(|(index, word)| if index == 0 { word.clone() } else { capitalize(word) })
+                    .collect()
+            }
+            RenameRule::Snake => lower.join("_"),
+            RenameRule::ScreamingSnake => lower.join("_").to_uppercase(),
+            RenameRule::Kebab => lower.join("-"),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,498 @@
+use proc_macro2::{Span, TokenStream};
+use syn;
+use syn::{Data, DataEnum, DeriveInput, Fields, FieldsNamed, GenericParam, Generics, Ident, Index, Lifetime,
+          LifetimeParam};
+
+use attr::{ContainerAttrs, Default, FieldAttrs, RenameRule, VariantAttrs};
+
+/// How an enum is represented in JavaScript.
+enum Repr {
+    /// Variant names as strings, for enums without data.
+    String,
+    /// Discriminants as numbers, for enums without data.
+    Number,
+    /// Objects whose `tag` property names the variant.
+    Tagged(String),
+    /// The variant's data alone, tried in order when converting back.
+    Untagged,
+}
+
+pub fn expand_into(input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let name = &input.ident;
+    let body = match input.data {
+        Data::Struct(ref data) => into_struct(&container, &data.fields)?,
+        Data::Enum(ref data) => into_enum(&container, name, data)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new(Span::call_site(),
+                                       "IntoNapiValue cannot be derived for unions"))
+        }
+    };
+
+    let generics = add_bounds(&input.generics, quote!(::node_api::IntoNapiValue));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::node_api::IntoNapiValue for #name #ty_generics #where_clause {
+            fn into_napi_value(self, env: ::node_api::NapiEnv)
+                               -> ::node_api::error::Result<::node_api::NapiValue> {
+                #body
+            }
+        }
+    })
+}
+
+pub fn expand_from(input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let name = &input.ident;
+    let body = match input.data {
+        Data::Struct(ref data) => from_struct(&container, name, &data.fields)?,
+        Data::Enum(ref data) => from_enum(&container, name, data)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new(Span::call_site(),
+                                       "FromNapiValue cannot be derived for unions"))
+        }
+    };
+
+    // Types holding handles read them in the scope of their own lifetime,
+    // others in any scope.
+    let mut generics = input.generics.clone();
+    let scope = match generics.lifetimes().next() {
+        Some(param) => param.lifetime.clone(),
+        None => {
+            let scope = Lifetime::new("'__napi", Span::call_site());
+            generics.params.insert(0, GenericParam::Lifetime(LifetimeParam::new(scope.clone())));
+            scope
+        }
+    };
+    let generics = add_bounds(&generics, quote!(::node_api::FromNapiValue<#scope>));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::node_api::FromNapiValue<#scope> for #name #ty_generics #where_clause {
+            fn from_napi_value(env: ::node_api::Env<#scope>, value: ::node_api::JsValue<#scope>)
+                               -> ::node_api::error::Result<Self> {
+                #body
+            }
+        }
+    })
+}
+
+fn into_struct(container: &ContainerAttrs, fields: &Fields) -> syn::Result<TokenStream> {
+    match *fields {
+        Fields::Named(ref named) => {
+            let sets = set_fields(container.rename_all, named, |ident| quote!(self.#ident))?;
+            Ok(quote! {
+                let __napi_object = ::node_api::napi::create_object(env)?;
+                #(#sets)*
+                Ok(__napi_object)
+            })
+        }
+        Fields::Unnamed(ref unnamed) if unnamed.unnamed.len() == 1 => {
+            Ok(quote!(::node_api::IntoNapiValue::into_napi_value(self.0, env)))
+        }
+        Fields::Unnamed(ref unnamed) => {
+            Ok(into_array(unnamed.unnamed.len(), |index| {
+                let member = Index::from(index);
+                quote!(self.#member)
+            }))
+        }
+        Fields::Unit => Ok(quote!(::node_api::napi::get_undefined(env))),
+    }
+}
+
+fn from_struct(container: &ContainerAttrs, name: &Ident, fields: &Fields) -> syn::Result<TokenStream> {
+    let type_name = name.to_string();
+    match *fields {
+        Fields::Named(ref named) => {
+            let inits = get_fields(container.rename_all, named)?;
+            Ok(quote! {
+                ::node_api::export::check_object(env, value, #type_name)?;
+                Ok(#name { #(#inits),* })
+            })
+        }
+        Fields::Unnamed(ref unnamed) if unnamed.unnamed.len() == 1 => {
+            Ok(quote!(Ok(#name(::node_api::FromNapiValue::from_napi_value(env, value)?))))
+        }
+        Fields::Unnamed(ref unnamed) => {
+            let length = unnamed.unnamed.len();
+            let inits = get_elements(length);
+            Ok(quote! {
+                ::node_api::export::check_array(env, value, #type_name, #length)?;
+                Ok(#name(#(#inits),*))
+            })
+        }
+        Fields::Unit => {
+            Ok(quote! {
+                ::node_api::export::check_unit(env, value, #type_name)?;
+                Ok(#name)
+            })
+        }
+    }
+}
+
+fn into_enum(container: &ContainerAttrs, name: &Ident, data: &DataEnum) -> syn::Result<TokenStream> {
+    let repr = enum_repr(container, data)?;
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let attrs = VariantAttrs::parse(&variant.attrs)?;
+        let ident = &variant.ident;
+        let js_name = variant_name(container, &attrs, ident);
+        let arm = match (&repr, &variant.fields) {
+            (Repr::String, _) => {
+                quote!(#name::#ident => ::node_api::napi::create_string_utf8(env, #js_name))
+            }
+            (Repr::Number, _) => {
+                quote!(#name::#ident => ::node_api::napi::create_double(env, #name::#ident as i64 as f64))
+            }
+            (Repr::Tagged(tag), fields) => {
+                let (pattern, sets) = match *fields {
+                    Fields::Named(ref named) => {
+                        let bound = bound_fields(named)?;
+                        let sets = set_fields(attrs.rename_all, named, |ident| {
+                            let binding = field_binding(ident);
+                            quote!(#binding)
+                        })?;
+                        (quote!(#name::#ident { #(#bound,)* .. }), sets)
+                    }
+                    // `enum_repr` only lets newtype variants through.
+                    Fields::Unnamed(_) => {
+                        let sets = vec![quote! {
+                            let __napi_content = ::node_api::IntoNapiValue::into_napi_value(__napi_field, env)?;
+                            ::node_api::napi::set_named_property(env, __napi_object, "value", __napi_content)?;
+                        }];
+                        (quote!(#name::#ident(__napi_field)), sets)
+                    }
+                    Fields::Unit => (quote!(#name::#ident), Vec::new()),
+                };
+                quote! {
+                    #pattern => {
+                        let __napi_object = ::node_api::napi::create_object(env)?;
+                        let __napi_tag = ::node_api::napi::create_string_utf8(env, #js_name)?;
+                        ::node_api::napi::set_named_property(env, __napi_object, #tag, __napi_tag)?;
+                        #(#sets)*
+                        Ok(__napi_object)
+                    }
+                }
+            }
+            (Repr::Untagged, Fields::Named(named)) => {
+                let bound = bound_fields(named)?;
+                let sets = set_fields(attrs.rename_all, named, |ident| {
+                    let binding = field_binding(ident);
+                    quote!(#binding)
+                })?;
+                quote! {
+                    #name::#ident { #(#bound,)* .. } => {
+                        let __napi_object = ::node_api::napi::create_object(env)?;
+                        #(#sets)*
+                        Ok(__napi_object)
+                    }
+                }
+            }
+            (Repr::Untagged, Fields::Unnamed(unnamed)) if unnamed.unnamed.len() == 1 => {
+                quote!(#name::#ident(__napi_field) => ::node_api::IntoNapiValue::into_napi_value(__napi_field, env))
+            }
+            (Repr::Untagged, Fields::Unnamed(unnamed)) => {
+                let bound = tuple_bindings(unnamed.unnamed.len());
+                let body = into_array(bound.len(), |index| {
+                    let field = &bound[index];
+                    quote!(#field)
+                });
+                quote!(#name::#ident(#(#bound),*) => { #body })
+            }
+            (Repr::Untagged, Fields::Unit) => {
+                quote!(#name::#ident => ::node_api::napi::get_null(env))
+            }
+        };
+        arms.push(arm);
+    }
+    Ok(quote! {
+        match self {
+            #(#arms,)*
+        }
+    })
+}
+
+fn from_enum(container: &ContainerAttrs, name: &Ident, data: &DataEnum) -> syn::Result<TokenStream> {
+    let repr = enum_repr(container, data)?;
+    let type_name = name.to_string();
+    let mut idents = Vec::new();
+    let mut js_names = Vec::new();
+    let mut variant_attrs = Vec::new();
+    for variant in &data.variants {
+        let attrs = VariantAttrs::parse(&variant.attrs)?;
+        idents.push(&variant.ident);
+        js_names.push(variant_name(container, &attrs, &variant.ident));
+        variant_attrs.push(attrs);
+    }
+
+    match repr {
+        Repr::String => {
+            Ok(quote! {
+                let variants = &[#(#js_names),*];
+                let variant = ::node_api::export::get_variant_name(env, value, #type_name, variants)?;
+                match &variant[..] {
+                    #(#js_names => Ok(#name::#idents),)*
+                    _ => Err(::node_api::export::unknown_variant(#type_name, &variant, variants)),
+                }
+            })
+        }
+        Repr::Number => {
+            Ok(quote! {
+                let number = ::node_api::export::get_variant_number(env, value, #type_name)?;
+                #(
+                    if number == #name::#idents as i64 as f64 {
+                        return Ok(#name::#idents);
+                    }
+                )*
+                Err(::node_api::export::unknown_numeric_variant(
+                    #type_name, number, &[#((#name::#idents as i64, #js_names)),*]))
+            })
+        }
+        Repr::Tagged(tag) => {
+            let mut arms = Vec::new();
+            for (variant, attrs) in data.variants.iter().zip(&variant_attrs) {
+                let ident = &variant.ident;
+                let init = match variant.fields {
+                    Fields::Named(ref named) => {
+                        let inits = get_fields(attrs.rename_all, named)?;
+                        quote!(#name::#ident { #(#inits),* })
+                    }
+                    Fields::Unnamed(_) => {
+                        quote!(#name::#ident(::node_api::export::get_field(env, value, "value")?))
+                    }
+                    Fields::Unit => quote!(#name::#ident),
+                };
+                arms.push(init);
+            }
+            Ok(quote! {
+                let variants = &[#(#js_names),*];
+                ::node_api::export::check_object(env, value, #type_name)?;
+                let variant = ::node_api::export::get_variant_tag(env, value, #type_name, #tag, variants)?;
+                match &variant[..] {
+                    #(#js_names => Ok(#arms),)*
+                    _ => Err(::node_api::export::unknown_variant(#type_name, &variant, variants)),
+                }
+            })
+        }
+        Repr::Untagged => {
+            let mut attempts = Vec::new();
+            for (variant, attrs) in data.variants.iter().zip(&variant_attrs) {
+                let ident = &variant.ident;
+                let variant_name = format!("{}::{}", type_name, ident);
+                let body = match variant.fields {
+                    Fields::Named(ref named) => {
+                        let inits = get_fields(attrs.rename_all, named)?;
+                        quote! {
+                            ::node_api::export::check_object(env, value, #variant_name)?;
+                            Ok(#name::#ident { #(#inits),* })
+                        }
+                    }
+                    Fields::Unnamed(ref unnamed) if unnamed.unnamed.len() == 1 => {
+                        quote!(Ok(#name::#ident(::node_api::FromNapiValue::from_napi_value(env, value)?)))
+                    }
+                    Fields::Unnamed(ref unnamed) => {
+                        let length = unnamed.unnamed.len();
+                        let inits = get_elements(length);
+                        quote! {
+                            ::node_api::export::check_array(env, value, #variant_name, #length)?;
+                            Ok(#name::#ident(#(#inits),*))
+                        }
+                    }
+                    Fields::Unit => {
+                        quote! {
+                            ::node_api::export::check_unit(env, value, #variant_name)?;
+                            Ok(#name::#ident)
+                        }
+                    }
+                };
+                attempts.push(body);
+            }
+            Ok(quote! {
+                #(
+                    let attempt = || -> ::node_api::error::Result<Self> { #attempts };
+                    if let Ok(result) = attempt() {
+                        return Ok(result);
+                    }
+                )*
+                Err(::node_api::export::no_matching_variant(#type_name, &[#(#js_names),*]))
+            })
+        }
+    }
+}
+
+fn enum_repr(container: &ContainerAttrs, data: &DataEnum) -> syn::Result<Repr> {
+    let unit_only = data.variants.iter().all(|variant| matches!(variant.fields, Fields::Unit));
+    if container.numeric {
+        if !unit_only {
+            return Err(syn::Error::new(Span::call_site(),
+                                       "#[napi(numeric)] requires an enum without data"));
+        }
+        return Ok(Repr::Number);
+    }
+    if container.untagged {
+        return Ok(Repr::Untagged);
+    }
+    let tag = match container.tag {
+        Some(ref tag) => tag.clone(),
+        None if unit_only => return Ok(Repr::String),
+        None => "type".to_string(),
+    };
+    for variant in &data.variants {
+        if let Fields::Unnamed(ref unnamed) = variant.fields {
+            if unnamed.unnamed.is_empty() {
+                return Err(syn::Error::new_spanned(variant,
+                                                   "tagged enums cannot contain empty tuple variants, \
+                                                    use a unit variant"));
+            }
+            if unnamed.unnamed.len() > 1 {
+                return Err(syn::Error::new_spanned(variant,
+                                                   "tagged enums cannot contain tuple variants, \
+                                                    use #[napi(untagged)]"));
+            }
+        }
+    }
+    Ok(Repr::Tagged(tag))
+}
+
+/// Statements setting the properties of `__napi_object` from named fields, each
+/// read through `access`.
+fn set_fields<F>(rule: Option<RenameRule>, fields: &FieldsNamed, access: F) -> syn::Result<Vec<TokenStream>>
+    where F: Fn(&Ident) -> TokenStream
+{
+    let mut sets = Vec::new();
+    for field in &fields.named {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let value = access(ident);
+        if attrs.flatten {
+            sets.push(quote! {
+                let __napi_nested = ::node_api::IntoNapiValue::into_napi_value(#value, env)?;
+                ::node_api::export::copy_properties(env, __napi_nested, __napi_object)?;
+            });
+        } else {
+            let key = property_name(rule, &attrs, ident);
+            sets.push(quote! {
+                let __napi_property = ::node_api::IntoNapiValue::into_napi_value(#value, env)?;
+                ::node_api::napi::set_named_property(env, __napi_object, #key, __napi_property)?;
+            });
+        }
+    }
+    Ok(sets)
+}
+
+/// Field initializers reading named fields from the object `value`.
+fn get_fields(rule: Option<RenameRule>, fields: &FieldsNamed) -> syn::Result<Vec<TokenStream>> {
+    let mut inits = Vec::new();
+    for field in &fields.named {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let ident = field.ident.as_ref().unwrap();
+        let init = if attrs.skip {
+            default_value(&attrs.default)
+        } else if attrs.flatten {
+            quote!(::node_api::FromNapiValue::from_napi_value(env, value)?)
+        } else {
+            let key = property_name(rule, &attrs, ident);
+            match attrs.default {
+                Some(_) => {
+                    let default = default_value(&attrs.default);
+                    quote! {
+                        match ::node_api::export::get_optional_field(env, value, #key)? {
+                            Some(field) => field,
+                            None => #default,
+                        }
+                    }
+                }
+                None => quote!(::node_api::export::get_field(env, value, #key)?),
+            }
+        };
+        inits.push(quote!(#ident: #init));
+    }
+    Ok(inits)
+}
+
+/// The patterns binding the named fields of a variant to `field_binding`
+/// names, leaving out skipped ones.
+fn bound_fields(fields: &FieldsNamed) -> syn::Result<Vec<TokenStream>> {
+    let mut bound = Vec::new();
+    for field in &fields.named {
+        if !FieldAttrs::parse(&field.attrs)?.skip {
+            let ident = field.ident.as_ref().unwrap();
+            let binding = field_binding(ident);
+            bound.push(quote!(#ident: #binding));
+        }
+    }
+    Ok(bound)
+}
+
+/// The name a field is bound to in generated code, which cannot clash with
+/// the generated locals and parameters.
+fn field_binding(ident: &Ident) -> Ident {
+    let name = ident.to_string();
+    Ident::new(&format!("__napi_field_{}", name.trim_start_matches("r#")), Span::call_site())
+}
+
+fn tuple_bindings(length: usize) -> Vec<Ident> {
+    (0..length).map(|index| Ident::new(&format!("__napi_field{}", index), Span::call_site())).collect()
+}
+
+fn into_array<F>(length: usize, access: F) -> TokenStream
+    where F: Fn(usize) -> TokenStream
+{
+    let sets = (0..length).map(|index| {
+        let value = access(index);
+        quote! {
+            let __napi_element = ::node_api::IntoNapiValue::into_napi_value(#value, env)?;
+            ::node_api::napi::set_element(env, __napi_array, #index, __napi_element)?;
+        }
+    });
+    quote! {
+        let __napi_array = ::node_api::napi::array_with_length(env, #length)?;
+        #(#sets)*
+        Ok(__napi_array)
+    }
+}
+
+fn get_elements(length: usize) -> Vec<TokenStream> {
+    (0..length).map(|index| quote!(::node_api::export::get_element(env, value, #index)?)).collect()
+}
+
+fn property_name(rule: Option<RenameRule>, attrs: &FieldAttrs, ident: &Ident) -> String {
+    let name = ident.to_string();
+    let name = name.trim_start_matches("r#");
+    match (attrs.rename.as_ref(), rule) {
+        (Some(rename), _) => rename.clone(),
+        (None, Some(rule)) => rule.apply_to_field(name),
+        (None, None) => name.to_string(),
+    }
+}
+
+fn variant_name(container: &ContainerAttrs, attrs: &VariantAttrs, ident: &Ident) -> String {
+    let name = ident.to_string();
+    match (attrs.rename.as_ref(), container.rename_all) {
+        (Some(rename), _) => rename.clone(),
+        (None, Some(rule)) => rule.apply_to_variant(&name),
+        (None, None) => name,
+    }
+}
+
+fn default_value(default: &Option<Default>) -> TokenStream {
+    match *default {
+        Some(Default::Path(ref path)) => quote!(#path()),
+        _ => quote!(::std::default::Default::default()),
+    }
+}
+
+fn add_bounds(generics: &Generics, bound: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics.type_params().map(|param| param.ident.clone()).collect();
+    {
+        let where_clause = generics.make_where_clause();
+        for param in params {
+            where_clause.predicates.push(syn::parse_quote!(#param: #bound));
+        }
+    }
+    generics
+}
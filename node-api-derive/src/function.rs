@@ -0,0 +1,122 @@
+use proc_macro2::{Span, TokenStream};
+use syn;
+use syn::{FnArg, Ident, ItemFn, LitStr, Pat, Type};
+use syn::spanned::Spanned;
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let function: ItemFn = syn::parse2(item)?;
+    let js_name = parse_js_name(attr)?.unwrap_or_else(|| function.sig.ident.to_string());
+    check_signature(&function)?;
+
+    let ident = &function.sig.ident;
+    let create = Ident::new(&format!("__napi_create_{}", ident), Span::call_site());
+    let register = Ident::new(&format!("__NAPI_REGISTER_{}", ident), Span::call_site());
+
+    let mut conversions = Vec::new();
+    let mut call_args = Vec::new();
+    let mut index = 0usize;
+    for (position, input) in function.sig.inputs.iter().enumerate() {
+        let typed = match *input {
+            FnArg::Typed(ref typed) => typed,
+            FnArg::Receiver(ref receiver) => {
+                return Err(syn::Error::new(receiver.span(), "#[napi] cannot be used on methods"))
+            }
+        };
+        let arg = Ident::new(&format!("__arg{}", position), Span::call_site());
+        // The types are left to inference, as lifetimes in them refer to
+        // the scope of the call.
+        if is_env(&typed.ty) {
+            conversions.push(quote! {
+                let #arg = env;
+            });
+        } else {
+            let name = match *typed.pat {
+                Pat::Ident(ref pat) => pat.ident.to_string(),
+                _ => format!("{}", index),
+            };
+            conversions.push(quote! {
+                let #arg = args.get(#index, #name)?;
+            });
+            index += 1;
+        }
+        call_args.push(arg);
+    }
+
+    Ok(quote! {
+        #function
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        fn #create(env: ::node_api::NapiEnv) -> ::node_api::error::Result<::node_api::NapiValue> {
+            ::node_api::napi::create_function_with_args(env, #js_name, |env, _, args| {
+                let args = ::node_api::export::Arguments::new(env, args);
+                #(#conversions)*
+                env.to_js(#ident(#(#call_args),*))
+            })
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+        compile_error!("#[napi] functions are registered from static constructors, \
+                        which are only supported on Linux, macOS and Windows");
+
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        #[used]
+        #[cfg_attr(target_os = "linux", link_section = ".init_array")]
+        #[cfg_attr(target_os = "macos", link_section = "__DATA,__mod_init_func")]
+        #[cfg_attr(target_os = "windows", link_section = ".CRT$XCU")]
+        static #register: extern "C" fn() = {
+            extern "C" fn __register_napi_export() {
+                ::node_api::export::register_export(#js_name, #create);
+            }
+            __register_napi_export
+        };
+    })
+}
+
+fn parse_js_name(attr: TokenStream) -> syn::Result<Option<String>> {
+    let mut js_name = None;
+    if attr.is_empty() {
+        return Ok(js_name);
+    }
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("js_name") {
+            let name: LitStr = meta.value()?.parse()?;
+            js_name = Some(name.value());
+            Ok(())
+        } else {
+            Err(meta.error("unsupported #[napi] attribute, expected `js_name`"))
+        }
+    });
+    syn::parse::Parser::parse2(parser, attr)?;
+    Ok(js_name)
+}
+
+fn check_signature(function: &ItemFn) -> syn::Result<()> {
+    let sig = &function.sig;
+    if sig.generics.type_params().next().is_some() {
+        return Err(syn::Error::new(sig.generics.span(), "#[napi] functions cannot be generic"));
+    }
+    if let Some(ref asyncness) = sig.asyncness {
+        return Err(syn::Error::new(asyncness.span(), "#[napi] functions cannot be async"));
+    }
+    if let Some(ref variadic) = sig.variadic {
+        return Err(syn::Error::new(variadic.span(), "#[napi] functions cannot be variadic"));
+    }
+    Ok(())
+}
+
+/// Whether `ty` is `Env`, `node_api::Env` or `::node_api::Env`. Other types
+/// named `Env` are converted from arguments like any other type.
+fn is_env(ty: &Type) -> bool {
+    let path = match *ty {
+        Type::Path(ref path) if path.qself.is_none() => &path.path,
+        _ => return false,
+    };
+    let names: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+    match names.len() {
+        1 => names[0] == "Env" && path.leading_colon.is_none(),
+        2 => names[0] == "node_api" && names[1] == "Env",
+        _ => false,
+    }
+}
@@ -0,0 +1,99 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+mod attr;
+mod convert;
+mod function;
+
+use proc_macro::TokenStream;
+use syn::DeriveInput;
+
+/// Exports a plain Rust function to JavaScript.
+///
+/// ```ignore
+/// #[napi]
+/// fn add(a: f64, b: f64) -> f64 {
+///     a + b
+/// }
+/// ```
+///
+/// Arguments are converted with `FromNapiValue` and the return value with
+/// `IntoNapiValue`. A parameter of type `Env` receives the environment of the
+/// call instead of an argument. The function is added to the exports of the
+/// module declared with `napi_module!` under its own name, or under the name
+/// given with `#[napi(js_name = "...")]`.
+///
+/// Handles such as `JsValue` are only valid during the call, so functions
+/// taking or returning them name its scope with a lifetime parameter:
+///
+/// ```ignore
+/// #[napi]
+/// fn first<'a>(env: Env<'a>, values: Vec<JsValue<'a>>) -> Result<JsValue<'a>> {
+///     values.first().cloned().map_or_else(|| env.get_undefined(), Ok)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn napi(attr: TokenStream, item: TokenStream) -> TokenStream {
+    function::expand(attr.into(), item.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derives `IntoNapiValue` for a struct or enum.
+///
+/// Structs with named fields become objects, tuple structs become arrays,
+/// newtypes become their inner value and unit structs become `undefined`.
+///
+/// Enums without data become the names of their variants, or their
+/// discriminants with `#[napi(numeric)]`. Enums with data become objects
+/// tagged with the variant name in a `type` property, `{ type: "Circle",
+/// radius: 1 }`. The data of a newtype variant goes in a `value` property,
+/// `{ type: "Square", value: 2 }`. `#[napi(tag = "kind")]` picks another
+/// property and `#[napi(untagged)]` leaves the tag out.
+///
+/// Tagged enums cannot contain tuple variants with more or less than one
+/// field:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate node_api;
+///
+/// #[derive(IntoNapiValue)]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     Empty(),
+/// }
+/// # fn main() {}
+/// ```
+///
+/// Supported attributes:
+///
+/// * `#[napi(rename_all = "camelCase")]` on a struct renames all properties,
+///   on an enum all variants and on a variant the properties of its fields
+/// * `#[napi(rename = "name")]` sets the name of a field or variant
+/// * `#[napi(skip)]` leaves a field out
+/// * `#[napi(flatten)]` merges the properties of a nested struct
+#[proc_macro_derive(IntoNapiValue, attributes(napi))]
+pub fn derive_into_napi_value(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse_macro_input!(input);
+    convert::expand_into(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+/// Derives `FromNapiValue` for a struct or enum, accepting the values
+/// `#[derive(IntoNapiValue)]` produces.
+///
+/// In addition to the attributes of `IntoNapiValue`, `#[napi(default)]` and
+/// `#[napi(default = "path::to::fn")]` fill in fields whose property is
+/// missing. Skipped fields are always filled in with their default.
+///
+/// Untagged enums try their variants in order and take the first one that
+/// converts. Values that match no variant are rejected with an error listing
+/// the accepted ones.
+#[proc_macro_derive(FromNapiValue, attributes(napi))]
+pub fn derive_from_napi_value(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse_macro_input!(input);
+    convert::expand_from(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
@@ -1,4 +1,4 @@
-#![allow(non_camel_case_types)]
+#![allow(non_camel_case_types, non_snake_case)]
 /* automatically generated by rust-bindgen */
 
 pub type char16_t = u16;
@@ -80,6 +80,7 @@ pub enum napi_valuetype {
     napi_object = 6,
     napi_function = 7,
     napi_external = 8,
+    napi_bigint = 9,
 }
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -94,22 +95,40 @@ pub enum napi_typedarray_type {
     napi_float32_array = 7,
     napi_float64_array = 8,
 }
-#[repr(u32)]
+/// A status returned by N-API functions.
+///
+/// This is a newtype rather than an enum because newer versions of Node.js
+/// add statuses, and receiving a value that is not a variant of a Rust enum
+/// would be undefined behavior.
+#[repr(transparent)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum napi_status {
-    napi_ok = 0,
-    napi_invalid_arg = 1,
-    napi_object_expected = 2,
-    napi_string_expected = 3,
-    napi_name_expected = 4,
-    napi_function_expected = 5,
-    napi_number_expected = 6,
-    napi_boolean_expected = 7,
-    napi_array_expected = 8,
-    napi_generic_failure = 9,
-    napi_pending_exception = 10,
-    napi_cancelled = 11,
-    napi_escape_called_twice = 12,
+pub struct napi_status(pub u32);
+#[allow(non_upper_case_globals)]
+impl napi_status {
+    pub const napi_ok: napi_status = napi_status(0);
+    pub const napi_invalid_arg: napi_status = napi_status(1);
+    pub const napi_object_expected: napi_status = napi_status(2);
+    pub const napi_string_expected: napi_status = napi_status(3);
+    pub const napi_name_expected: napi_status = napi_status(4);
+    pub const napi_function_expected: napi_status = napi_status(5);
+    pub const napi_number_expected: napi_status = napi_status(6);
+    pub const napi_boolean_expected: napi_status = napi_status(7);
+    pub const napi_array_expected: napi_status = napi_status(8);
+    pub const napi_generic_failure: napi_status = napi_status(9);
+    pub const napi_pending_exception: napi_status = napi_status(10);
+    pub const napi_cancelled: napi_status = napi_status(11);
+    pub const napi_escape_called_twice: napi_status = napi_status(12);
+    pub const napi_handle_scope_mismatch: napi_status = napi_status(13);
+    pub const napi_callback_scope_mismatch: napi_status = napi_status(14);
+    pub const napi_queue_full: napi_status = napi_status(15);
+    pub const napi_closing: napi_status = napi_status(16);
+    pub const napi_bigint_expected: napi_status = napi_status(17);
+    pub const napi_date_expected: napi_status = napi_status(18);
+    pub const napi_arraybuffer_expected: napi_status = napi_status(19);
+    pub const napi_detachable_arraybuffer_expected: napi_status = napi_status(20);
+    pub const napi_would_deadlock: napi_status = napi_status(21);
+    pub const napi_no_external_buffers_allowed: napi_status = napi_status(22);
+    pub const napi_cannot_run_js: napi_status = napi_status(23);
 }
 pub type napi_callback =
     ::std::option::Option<unsafe extern "C" fn(env: napi_env,
@@ -150,46 +169,30 @@ fn bindgen_test_layout_napi_property_descriptor() {
     assert_eq! (::std::mem::align_of::<napi_property_descriptor>() , 8usize ,
                 concat ! (
                 "Alignment of " , stringify ! ( napi_property_descriptor ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . utf8name
-                as * const _ as usize } , 0usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, utf8name) , 0usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( utf8name )
                 ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . name as *
-                const _ as usize } , 8usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, name) , 8usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( name ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . method as
-                * const _ as usize } , 16usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, method) , 16usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( method ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . getter as
-                * const _ as usize } , 24usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, getter) , 24usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( getter ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . setter as
-                * const _ as usize } , 32usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, setter) , 32usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( setter ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . value as *
-                const _ as usize } , 40usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, value) , 40usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( value ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . attributes
-                as * const _ as usize } , 48usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, attributes) , 48usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( attributes )
                 ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_property_descriptor ) ) . data as *
-                const _ as usize } , 56usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_property_descriptor, data) , 56usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_property_descriptor ) , "::" , stringify ! ( data ) ));
 }
@@ -212,28 +215,20 @@ fn bindgen_test_layout_napi_extended_error_info() {
     assert_eq! (::std::mem::align_of::<napi_extended_error_info>() , 8usize ,
                 concat ! (
                 "Alignment of " , stringify ! ( napi_extended_error_info ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_extended_error_info ) ) .
-                error_message as * const _ as usize } , 0usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_extended_error_info, error_message) , 0usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_extended_error_info ) , "::" , stringify ! (
                 error_message ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_extended_error_info ) ) .
-                engine_reserved as * const _ as usize } , 8usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_extended_error_info, engine_reserved) , 8usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_extended_error_info ) , "::" , stringify ! (
                 engine_reserved ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_extended_error_info ) ) .
-                engine_error_code as * const _ as usize } , 16usize , concat !
+    assert_eq! (::std::mem::offset_of!(napi_extended_error_info, engine_error_code) , 16usize , concat !
                 (
                 "Alignment of field: " , stringify ! (
                 napi_extended_error_info ) , "::" , stringify ! (
                 engine_error_code ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_extended_error_info ) ) . error_code
-                as * const _ as usize } , 20usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_extended_error_info, error_code) , 20usize , concat ! (
                 "Alignment of field: " , stringify ! (
                 napi_extended_error_info ) , "::" , stringify ! ( error_code )
                 ));
@@ -255,24 +250,16 @@ fn bindgen_test_layout_napi_node_version() {
                ( "Size of: " , stringify ! ( napi_node_version ) ));
     assert_eq! (::std::mem::align_of::<napi_node_version>() , 8usize , concat
                 ! ( "Alignment of " , stringify ! ( napi_node_version ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_node_version ) ) . major as * const
-                _ as usize } , 0usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_node_version, major) , 0usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_node_version ) ,
                 "::" , stringify ! ( major ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_node_version ) ) . minor as * const
-                _ as usize } , 4usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_node_version, minor) , 4usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_node_version ) ,
                 "::" , stringify ! ( minor ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_node_version ) ) . patch as * const
-                _ as usize } , 8usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_node_version, patch) , 8usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_node_version ) ,
                 "::" , stringify ! ( patch ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_node_version ) ) . release as *
-                const _ as usize } , 16usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_node_version, release) , 16usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_node_version ) ,
                 "::" , stringify ! ( release ) ));
 }
@@ -300,39 +287,25 @@ fn bindgen_test_layout_napi_module() {
                "Size of: " , stringify ! ( napi_module ) ));
     assert_eq! (::std::mem::align_of::<napi_module>() , 8usize , concat ! (
                 "Alignment of " , stringify ! ( napi_module ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . nm_version as * const _
-                as usize } , 0usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, nm_version) , 0usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( nm_version ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . nm_flags as * const _
-                as usize } , 4usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, nm_flags) , 4usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( nm_flags ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . nm_filename as * const
-                _ as usize } , 8usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, nm_filename) , 8usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( nm_filename ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . nm_register_func as *
-                const _ as usize } , 16usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, nm_register_func) , 16usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( nm_register_func ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . nm_modname as * const _
-                as usize } , 24usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, nm_modname) , 24usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( nm_modname ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . nm_priv as * const _ as
-                usize } , 32usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, nm_priv) , 32usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( nm_priv ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const napi_module ) ) . reserved as * const _
-                as usize } , 40usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(napi_module, reserved) , 40usize , concat ! (
                 "Alignment of field: " , stringify ! ( napi_module ) , "::" ,
                 stringify ! ( reserved ) ));
 }
@@ -462,6 +435,36 @@ extern "C" {
     pub fn napi_get_value_int64(env: napi_env, value: napi_value,
                                 result: *mut i64) -> napi_status;
 }
+extern "C" {
+    pub fn napi_create_bigint_int64(env: napi_env, value: i64,
+                                    result: *mut napi_value) -> napi_status;
+}
+extern "C" {
+    pub fn napi_create_bigint_uint64(env: napi_env, value: u64,
+                                     result: *mut napi_value) -> napi_status;
+}
+extern "C" {
+    pub fn napi_create_bigint_words(env: napi_env,
+                                    sign_bit: ::std::os::raw::c_int,
+                                    word_count: usize, words: *const u64,
+                                    result: *mut napi_value) -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_value_bigint_int64(env: napi_env, value: napi_value,
+                                       result: *mut i64, lossless: *mut bool)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_value_bigint_uint64(env: napi_env, value: napi_value,
+                                        result: *mut u64, lossless: *mut bool)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_value_bigint_words(env: napi_env, value: napi_value,
+                                       sign_bit: *mut ::std::os::raw::c_int,
+                                       word_count: *mut usize,
+                                       words: *mut u64) -> napi_status;
+}
 extern "C" {
     pub fn napi_get_value_bool(env: napi_env, value: napi_value,
                                result: *mut bool) -> napi_status;
@@ -577,6 +580,18 @@ extern "C" {
     pub fn napi_get_array_length(env: napi_env, value: napi_value,
                                  result: *mut u32) -> napi_status;
 }
+extern "C" {
+    pub fn napi_create_date(env: napi_env, time: f64, result: *mut napi_value)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_is_date(env: napi_env, value: napi_value, is_date: *mut bool)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_date_value(env: napi_env, value: napi_value,
+                               result: *mut f64) -> napi_status;
+}
 extern "C" {
     pub fn napi_strict_equals(env: napi_env, lhs: napi_value, rhs: napi_value,
                               result: *mut bool) -> napi_status;
@@ -624,6 +639,13 @@ extern "C" {
                      finalize_hint: *mut ::std::os::raw::c_void,
                      result: *mut napi_ref) -> napi_status;
 }
+extern "C" {
+    pub fn napi_add_finalizer(env: napi_env, js_object: napi_value,
+                              finalize_data: *mut ::std::os::raw::c_void,
+                              finalize_cb: napi_finalize,
+                              finalize_hint: *mut ::std::os::raw::c_void,
+                              result: *mut napi_ref) -> napi_status;
+}
 extern "C" {
     pub fn napi_unwrap(env: napi_env, js_object: napi_value,
                        result: *mut *mut ::std::os::raw::c_void)
@@ -655,6 +677,12 @@ extern "C" {
     pub fn napi_delete_reference(env: napi_env, ref_: napi_ref)
      -> napi_status;
 }
+extern "C" {
+    pub fn napi_add_env_cleanup_hook(env: napi_env,
+                                     fun: ::std::option::Option<unsafe extern "C" fn(arg: *mut ::std::os::raw::c_void)>,
+                                     arg: *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
 extern "C" {
     pub fn napi_reference_ref(env: napi_env, ref_: napi_ref, result: *mut u32)
      -> napi_status;
@@ -903,24 +931,16 @@ fn bindgen_test_layout___va_list_tag() {
                "Size of: " , stringify ! ( __va_list_tag ) ));
     assert_eq! (::std::mem::align_of::<__va_list_tag>() , 8usize , concat ! (
                 "Alignment of " , stringify ! ( __va_list_tag ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const __va_list_tag ) ) . gp_offset as * const
-                _ as usize } , 0usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(__va_list_tag, gp_offset) , 0usize , concat ! (
                 "Alignment of field: " , stringify ! ( __va_list_tag ) , "::"
                 , stringify ! ( gp_offset ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const __va_list_tag ) ) . fp_offset as * const
-                _ as usize } , 4usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(__va_list_tag, fp_offset) , 4usize , concat ! (
                 "Alignment of field: " , stringify ! ( __va_list_tag ) , "::"
                 , stringify ! ( fp_offset ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const __va_list_tag ) ) . overflow_arg_area as
-                * const _ as usize } , 8usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(__va_list_tag, overflow_arg_area) , 8usize , concat ! (
                 "Alignment of field: " , stringify ! ( __va_list_tag ) , "::"
                 , stringify ! ( overflow_arg_area ) ));
-    assert_eq! (unsafe {
-                & ( * ( 0 as * const __va_list_tag ) ) . reg_save_area as *
-                const _ as usize } , 16usize , concat ! (
+    assert_eq! (::std::mem::offset_of!(__va_list_tag, reg_save_area) , 16usize , concat ! (
                 "Alignment of field: " , stringify ! ( __va_list_tag ) , "::"
                 , stringify ! ( reg_save_area ) ));
 }
@@ -0,0 +1,218 @@
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue, check_napi_type, check_integer, range_error,
+                 MAX_SAFE_INTEGER};
+use handle::JsValue;
+use env::Env;
+use error::{Result, NapiError, NapiErrorType};
+
+/// A JavaScript BigInt.
+///
+/// Conversions to Rust integers are lossless: BigInts that do not fit the
+/// requested type are rejected with a `RangeError` instead of being truncated.
+#[derive(Debug, Clone, Copy)]
+pub struct JsBigInt<'a> {
+    value: JsValue<'a>,
+}
+
+impl<'a> JsBigInt<'a> {
+    pub fn from_value(value: JsValue<'a>) -> Result<JsBigInt<'a>> {
+        check_napi_type(value.env().raw(), NapiValueType::BigInt, value.raw())?;
+        Ok(JsBigInt { value: value })
+    }
+
+    pub fn from_i64(env: Env<'a>, value: i64) -> Result<JsBigInt<'a>> {
+        let raw = napi::create_bigint_i64(env.raw(), value)?;
+        Ok(JsBigInt { value: unsafe { env.value(raw) } })
+    }
+
+    pub fn from_u64(env: Env<'a>, value: u64) -> Result<JsBigInt<'a>> {
+        let raw = napi::create_bigint_u64(env.raw(), value)?;
+        Ok(JsBigInt { value: unsafe { env.value(raw) } })
+    }
+
+    pub fn from_i128(env: Env<'a>, value: i128) -> Result<JsBigInt<'a>> {
+        let raw = create_bigint_i128(env.raw(), value)?;
+        Ok(JsBigInt { value: unsafe { env.value(raw) } })
+    }
+
+    pub fn from_u128(env: Env<'a>, value: u128) -> Result<JsBigInt<'a>> {
+        let raw = create_bigint_u128(env.raw(), value)?;
+        Ok(JsBigInt { value: unsafe { env.value(raw) } })
+    }
+
+    /// Creates a BigInt from its sign and its magnitude as 64-bit words,
+    /// least significant first.
+    pub fn from_words(env: Env<'a>, negative: bool, words: &[u64]) -> Result<JsBigInt<'a>> {
+        let raw = napi::create_bigint_words(env.raw(), negative, words)?;
+        Ok(JsBigInt { value: unsafe { env.value(raw) } })
+    }
+
+    pub fn value(&self) -> JsValue<'a> {
+        self.value
+    }
+
+    pub fn to_i64(&self) -> Result<i64> {
+        get_bigint_i64(self.value.env().raw(), self.value.raw())
+    }
+
+    pub fn to_u64(&self) -> Result<u64> {
+        get_bigint_u64(self.value.env().raw(), self.value.raw())
+    }
+
+    pub fn to_i128(&self) -> Result<i128> {
+        get_bigint_i128(self.value.env().raw(), self.value.raw())
+    }
+
+    pub fn to_u128(&self) -> Result<u128> {
+        get_bigint_u128(self.value.env().raw(), self.value.raw())
+    }
+
+    /// Returns the sign (`true` if negative) and the magnitude as 64-bit
+    /// words, least significant first. Works for BigInts of any size.
+    pub fn to_words(&self) -> Result<(bool, Vec<u64>)> {
+        napi::get_value_bigint_words(self.value.env().raw(), self.value.raw())
+    }
+}
+
+impl<'a> FromNapiValue<'a> for JsBigInt<'a> {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        JsBigInt::from_value(value)
+    }
+}
+
+impl<'a> IntoNapiValue for JsBigInt<'a> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value.raw())
+    }
+}
+
+/// 128-bit integers have no exact number representation and are always
+/// converted to and from BigInts.
+impl<'a> FromNapiValue<'a> for i128 {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<i128> {
+        check_napi_type(env.raw(), NapiValueType::BigInt, value.raw())?;
+        get_bigint_i128(env.raw(), value.raw())
+    }
+}
+
+impl<'a> FromNapiValue<'a> for u128 {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<u128> {
+        check_napi_type(env.raw(), NapiValueType::BigInt, value.raw())?;
+        get_bigint_u128(env.raw(), value.raw())
+    }
+}
+
+impl IntoNapiValue for i128 {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        create_bigint_i128(env, self)
+    }
+}
+
+impl IntoNapiValue for u128 {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        create_bigint_u128(env, self)
+    }
+}
+
+/// An integer that may be passed as either a BigInt or a number, for values
+/// such as IDs that can exceed `Number.MAX_SAFE_INTEGER`.
+///
+/// Numbers have to be integers within the range of `T` and BigInts have to
+/// fit `T` without loss. It is returned as a number if that represents the
+/// value exactly, and as a BigInt otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BigIntOrNumber<T>(pub T);
+
+macro_rules! impl_bigint_or_number {
+    ($t:ident, $get_bigint:expr, $create_bigint:expr) => {
+        impl<'a> FromNapiValue<'a> for BigIntOrNumber<$t> {
+            fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+                match value.type_of()? {
+                    NapiValueType::BigInt => $get_bigint(env.raw(), value.raw()).map(BigIntOrNumber),
+                    NapiValueType::Number => {
+                        let number = env.get_value_double(value)?;
+                        check_integer(number, $t::MIN as f64, $t::MAX as f64 + 1.0, stringify!($t), $t::MIN, $t::MAX)?;
+                        Ok(BigIntOrNumber(number as $t))
+                    }
+                    value_type => {
+                        Err(NapiError::new(NapiErrorType::InvalidArg,
+                                           format!("expected a BigInt or a number, but found {:?}", value_type)))
+                    }
+                }
+            }
+        }
+
+        impl IntoNapiValue for BigIntOrNumber<$t> {
+            fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+                // Values beyond the safe range round to at least 2^53, so
+                // this never passes an inexact number.
+                let number = self.0 as f64;
+                if number.abs() <= MAX_SAFE_INTEGER {
+                    napi::create_double(env, number)
+                } else {
+                    $create_bigint(env, self.0)
+                }
+            }
+        }
+    }
+}
+
+impl_bigint_or_number!(i64,  get_bigint_i64,  napi::create_bigint_i64);
+impl_bigint_or_number!(u64,  get_bigint_u64,  napi::create_bigint_u64);
+impl_bigint_or_number!(i128, get_bigint_i128, create_bigint_i128);
+impl_bigint_or_number!(u128, get_bigint_u128, create_bigint_u128);
+
+fn get_bigint_i64(env: NapiEnv, value: NapiValue) -> Result<i64> {
+    match napi::get_value_bigint_i64(env, value)? {
+        (result, true) => Ok(result),
+        (_, false) => Err(range_error("BigInt is out of range for i64".to_string())),
+    }
+}
+
+fn get_bigint_u64(env: NapiEnv, value: NapiValue) -> Result<u64> {
+    match napi::get_value_bigint_u64(env, value)? {
+        (result, true) => Ok(result),
+        (_, false) => Err(range_error("BigInt is out of range for u64".to_string())),
+    }
+}
+
+/// Reads the sign and magnitude of a BigInt that fits into 128 bits.
+fn get_bigint_magnitude(env: NapiEnv, value: NapiValue, type_name: &str) -> Result<(bool, u128)> {
+    let (negative, mut words) = napi::get_value_bigint_words(env, value)?;
+    while words.last() == Some(&0) {
+        words.pop();
+    }
+    if words.len() > 2 {
+        return Err(range_error(format!("BigInt is out of range for {}", type_name)));
+    }
+    let magnitude = words.iter().rev().fold(0u128, |magnitude, &word| (magnitude << 64) | word as u128);
+    Ok((negative, magnitude))
+}
+
+pub(crate) fn get_bigint_i128(env: NapiEnv, value: NapiValue) -> Result<i128> {
+    let (negative, magnitude) = get_bigint_magnitude(env, value, "i128")?;
+    if negative && magnitude <= i128::MIN.unsigned_abs() {
+        Ok((magnitude as i128).wrapping_neg())
+    } else if !negative && magnitude <= i128::MAX as u128 {
+        Ok(magnitude as i128)
+    } else {
+        Err(range_error("BigInt is out of range for i128".to_string()))
+    }
+}
+
+pub(crate) fn get_bigint_u128(env: NapiEnv, value: NapiValue) -> Result<u128> {
+    match get_bigint_magnitude(env, value, "u128")? {
+        (true, magnitude) if magnitude != 0 => Err(range_error("BigInt is out of range for u128".to_string())),
+        (_, magnitude) => Ok(magnitude),
+    }
+}
+
+pub(crate) fn create_bigint_i128(env: NapiEnv, value: i128) -> Result<NapiValue> {
+    let magnitude = value.unsigned_abs();
+    napi::create_bigint_words(env, value < 0, &[magnitude as u64, (magnitude >> 64) as u64])
+}
+
+pub(crate) fn create_bigint_u128(env: NapiEnv, value: u128) -> Result<NapiValue> {
+    napi::create_bigint_words(env, false, &[value as u64, (value >> 64) as u64])
+}
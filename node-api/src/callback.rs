@@ -0,0 +1,101 @@
+use std::marker::PhantomData;
+
+use napi::{NapiEnv, NapiValue};
+use napi_value::{FromNapiValue, IntoNapiValue, IntoNapiValues};
+use handle::{JsValue, Reference};
+use function::JsFunction;
+use env::Env;
+use error::Result;
+
+/// A JavaScript function received as an argument, typed by the arguments it
+/// is called with and the value it returns.
+///
+/// A `JsCallback` is only valid during the native call it was passed to. Use
+/// `persist` to keep it around and call it later on the JavaScript thread.
+#[derive(Debug)]
+pub struct JsCallback<'a, Args, Ret> {
+    function: JsFunction<'a>,
+    _signature: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<'a, Args, Ret> JsCallback<'a, Args, Ret>
+    where Args: IntoNapiValues,
+          Ret: FromNapiValue<'a>
+{
+    pub fn new(function: JsFunction<'a>) -> JsCallback<'a, Args, Ret> {
+        JsCallback {
+            function: function,
+            _signature: PhantomData,
+        }
+    }
+
+    pub fn function(&self) -> JsFunction<'a> {
+        self.function
+    }
+
+    /// Calls the function with `undefined` as `this`.
+    pub fn call(&self, args: Args) -> Result<Ret> {
+        let this = self.function.value().env().get_undefined()?;
+        self.function.call(this, args)
+    }
+
+    pub fn call_with_this(&self, this: JsValue<'a>, args: Args) -> Result<Ret> {
+        self.function.call(this, args)
+    }
+
+    pub fn persist(&self) -> Result<PersistentCallback<Args, Ret>> {
+        Ok(PersistentCallback {
+               function: Reference::new(self.function.value())?,
+               _signature: PhantomData,
+           })
+    }
+}
+
+impl<'a, Args, Ret> Clone for JsCallback<'a, Args, Ret> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, Args, Ret> Copy for JsCallback<'a, Args, Ret> {}
+
+impl<'a, Args, Ret> FromNapiValue<'a> for JsCallback<'a, Args, Ret>
+    where Args: IntoNapiValues,
+          Ret: FromNapiValue<'a>
+{
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        JsFunction::from_napi_value(env, value).map(JsCallback::new)
+    }
+}
+
+impl<'a, Args, Ret> IntoNapiValue for JsCallback<'a, Args, Ret> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.function.value().raw())
+    }
+}
+
+/// A `JsCallback` that outlives the call it was received in.
+///
+/// It holds a reference to the function and can be called from any later
+/// callback on the JavaScript thread, e.g. when async work completes. As it
+/// is called in scopes that come after the one it was received in, it has to
+/// return values that own their data.
+#[derive(Debug)]
+pub struct PersistentCallback<Args, Ret> {
+    function: Reference,
+    _signature: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<Args, Ret> PersistentCallback<Args, Ret>
+    where Args: IntoNapiValues,
+          Ret: for<'a> FromNapiValue<'a>
+{
+    pub fn get<'a>(&self, env: Env<'a>) -> Result<JsCallback<'a, Args, Ret>> {
+        let function = JsFunction::from_value(self.function.get(env)?)?;
+        Ok(JsCallback::new(function))
+    }
+
+    pub fn call(&self, env: Env, args: Args) -> Result<Ret> {
+        self.get(env)?.call(args)
+    }
+}
@@ -0,0 +1,222 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::os;
+
+use node_api_sys::*;
+use napi;
+use napi::{NapiEnv, NapiRef, NapiValue};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue};
+use handle::JsValue;
+use env::Env;
+use error::{Result, NapiError, NapiErrorType};
+
+/// A JavaScript class whose instances can be recognised with `instanceof`.
+///
+/// `Exports::class` defines the class with `construct` and `properties` and
+/// keeps its constructor for `constructor`. Classes defined in JavaScript
+/// implement `constructor` instead, usually returning a constructor kept in
+/// a `Reference`, for example in a `thread_local!`.
+pub trait JsClass: 'static {
+    /// The name of the class, also used in error messages.
+    const NAME: &'static str;
+
+    /// Initialises `this`, an instance created with `new`, from the arguments
+    /// of the call. Does nothing by default.
+    fn construct<'a>(_env: Env<'a>, _this: JsValue<'a>, _args: &[JsValue<'a>]) -> Result<()> {
+        Ok(())
+    }
+
+    /// The methods and other properties of the class, none by default.
+    fn properties<'a>(_env: Env<'a>) -> Result<Vec<ClassProperty<'a>>> {
+        Ok(Vec::new())
+    }
+
+    /// The constructor of the class, by default the one defined by
+    /// `Exports::class` in `env`.
+    fn constructor<'a>(env: Env<'a>) -> Result<JsValue<'a>> {
+        defined_constructor::<Self>(env)
+    }
+}
+
+/// A method of a class, or a property of its constructor.
+pub struct ClassProperty<'a> {
+    descriptor: napi::PropertyDescriptor,
+    _scope: PhantomData<JsValue<'a>>,
+}
+
+impl<'a> ClassProperty<'a> {
+    /// A method of the instances, called with the instance as `this` and the
+    /// arguments converted to `T`, like a function created with
+    /// `Env::create_function`.
+    pub fn method<S, F, T, R>(name: S, f: F) -> ClassProperty<'a>
+        where S: Into<String>,
+              F: for<'s> Fn(Env<'s>, JsValue<'s>, T) -> R + 'static,
+              T: for<'s> FromNapiValues<'s>,
+              R: IntoNapiValue
+    {
+        let method: napi::Method = Box::new(move |env, this, args| {
+            let args = T::from_napi_values(env, this, args)?;
+            env.to_js(f(env, this, args))
+        });
+        ClassProperty {
+            descriptor: napi::PropertyDescriptor {
+                name: name.into(),
+                value: napi::PropertyValue::Method(method),
+                is_static: false,
+            },
+            _scope: PhantomData,
+        }
+    }
+
+    /// A property of the constructor, such as a constant.
+    pub fn static_value<S: Into<String>>(name: S, value: JsValue<'a>) -> ClassProperty<'a> {
+        ClassProperty {
+            descriptor: napi::PropertyDescriptor {
+                name: name.into(),
+                value: napi::PropertyValue::Value(value.raw()),
+                is_static: true,
+            },
+            _scope: PhantomData,
+        }
+    }
+
+    pub(crate) fn into_descriptor(self) -> napi::PropertyDescriptor {
+        self.descriptor
+    }
+}
+
+thread_local! {
+    /// The constructors defined by `define`, by environment and class. The
+    /// references are deleted when their environment is torn down.
+    static DEFINED: RefCell<Vec<(NapiEnv, TypeId, NapiRef)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Defines the class `T` in `env` and keeps its constructor for
+/// `JsClass::constructor`.
+pub(crate) fn define<'a, T: JsClass>(env: Env<'a>) -> Result<JsValue<'a>> {
+    let properties = T::properties(env)?;
+    let constructor = env.define_class(T::NAME, |env, this, args| {
+        T::construct(env, this, args)?;
+        Ok(this)
+    }, properties)?;
+    let reference = napi::create_reference(env.raw(), constructor.raw(), 1)?;
+    let first = DEFINED.with(|defined| {
+        !defined.borrow().iter().any(|&(defined_env, _, _)| defined_env == env.raw())
+    });
+    if first {
+        let status = unsafe {
+            napi_add_env_cleanup_hook(env.raw(), Some(forget_classes), env.raw() as *mut os::raw::c_void)
+        };
+        if status != napi_status::napi_ok {
+            napi::delete_reference(env.raw(), reference)?;
+            return Err(NapiError::new(NapiErrorType::from(status),
+                                      format!("cannot keep the constructor of {}", T::NAME)));
+        }
+    }
+    let replaced = DEFINED.with(|defined| {
+        let mut defined = defined.borrow_mut();
+        let class = TypeId::of::<T>();
+        match defined.iter_mut().find(|&&mut (defined_env, id, _)| defined_env == env.raw() && id == class) {
+            Some(entry) => Some(::std::mem::replace(&mut entry.2, reference)),
+            None => {
+                defined.push((env.raw(), class, reference));
+                None
+            }
+        }
+    });
+    if let Some(replaced) = replaced {
+        napi::delete_reference(env.raw(), replaced)?;
+    }
+    Ok(constructor)
+}
+
+fn defined_constructor<'a, T: JsClass + ?Sized>(env: Env<'a>) -> Result<JsValue<'a>> {
+    let class = TypeId::of::<T>();
+    let reference = DEFINED.with(|defined| {
+        defined.borrow()
+            .iter()
+            .find(|&&(defined_env, id, _)| defined_env == env.raw() && id == class)
+            .map(|&(_, _, reference)| reference)
+    });
+    match reference {
+        Some(reference) => {
+            let raw = napi::get_reference_value(env.raw(), reference)?;
+            Ok(unsafe { env.value(raw) })
+        }
+        None => Err(NapiError::new(NapiErrorType::GenericFailure, format!("{} is not defined", T::NAME))),
+    }
+}
+
+unsafe extern "C" fn forget_classes(env: *mut os::raw::c_void) {
+    let env = env as NapiEnv;
+    let forgotten: Vec<NapiRef> = DEFINED.try_with(|defined| {
+        let mut defined = defined.borrow_mut();
+        let forgotten = defined.iter()
+            .filter(|&&(defined_env, _, _)| defined_env == env)
+            .map(|&(_, _, reference)| reference)
+            .collect();
+        defined.retain(|&(defined_env, _, _)| defined_env != env);
+        forgotten
+    }).unwrap_or_default();
+    for reference in forgotten {
+        napi_delete_reference(env, reference);
+    }
+}
+
+/// An object that is an instance of the class `T`.
+///
+/// As an argument type it rejects values that are not `instanceof` the
+/// constructor of `T`, so a method can rely on having received one of its
+/// own objects rather than a lookalike with the same properties.
+#[derive(Debug)]
+pub struct Instance<'a, T> {
+    value: JsValue<'a>,
+    _class: PhantomData<T>,
+}
+
+impl<'a, T> Clone for Instance<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Instance<'a, T> {}
+
+impl<'a, T: JsClass> Instance<'a, T> {
+    pub fn from_value(value: JsValue<'a>) -> Result<Instance<'a, T>> {
+        let constructor = T::constructor(value.env())?;
+        check_instance(value.env().raw(), value.raw(), constructor.raw(), T::NAME)?;
+        Ok(Instance {
+            value: value,
+            _class: PhantomData,
+        })
+    }
+
+    pub fn value(&self) -> JsValue<'a> {
+        self.value
+    }
+}
+
+impl<'a, T: JsClass> FromNapiValue<'a> for Instance<'a, T> {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        Instance::from_value(value)
+    }
+}
+
+impl<'a, T> IntoNapiValue for Instance<'a, T> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value.raw())
+    }
+}
+
+fn check_instance(env: NapiEnv, value: NapiValue, constructor: NapiValue, class_name: &str) -> Result<()> {
+    if napi::instance_of(env, value, constructor)? {
+        Ok(())
+    } else {
+        Err(NapiError::new(NapiErrorType::InvalidArg,
+                           format!("expected an instance of {}, but found {:?}",
+                                   class_name,
+                                   napi::type_of(env, value)?)))
+    }
+}
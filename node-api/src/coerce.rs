@@ -0,0 +1,73 @@
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue};
+use string::JsStringUtf16;
+use handle::JsValue;
+use env::Env;
+use error::Result;
+
+/// An argument that accepts any JavaScript value and converts it the way
+/// JavaScript would, instead of requiring the exact type.
+///
+/// `Coerced<bool>` follows `Boolean(x)`, the number types follow `Number(x)`
+/// and `Coerced<String>` follows `String(x)`, so `"42"` is accepted as the
+/// number 42 and `42` as the string `"42"`. Coercion may call `valueOf` or
+/// `toString` on objects and throws where JavaScript would, for example
+/// when converting a symbol to a number. Integer types still reject numbers
+/// that are not integral or out of range, so `Coerced<u8>` accepts `"200"`
+/// but not `"1.5"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Coerced<T>(pub T);
+
+impl<T> IntoNapiValue for Coerced<T>
+    where T: IntoNapiValue
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        self.0.into_napi_value(env)
+    }
+}
+
+impl<'a> FromNapiValue<'a> for Coerced<bool> {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        let value = env.coerce_to_bool(value)?;
+        bool::from_napi_value(env, value).map(Coerced)
+    }
+}
+
+macro_rules! impl_coerced {
+    ($coerce:expr, $($t:ty),+) => {
+        $(
+            impl<'a> FromNapiValue<'a> for Coerced<$t> {
+                fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+                    let value = unsafe { env.value($coerce(env.raw(), value.raw())?) };
+                    <$t>::from_napi_value(env, value).map(Coerced)
+                }
+            }
+        )+
+    }
+}
+
+impl_coerced!(coerce_to_number, f64, f32, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_coerced!(coerce_to_string, String, JsStringUtf16);
+
+/// `ToNumber` throws for BigInts, which `Number(x)` converts.
+fn coerce_to_number(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    match napi::type_of(env, value)? {
+        NapiValueType::BigInt => call_global(env, "Number", value),
+        _ => napi::coerce_to_number(env, value),
+    }
+}
+
+/// `ToString` throws for symbols, which `String(x)` describes.
+fn coerce_to_string(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    match napi::type_of(env, value)? {
+        NapiValueType::Symbol => call_global(env, "String", value),
+        _ => napi::coerce_to_string(env, value),
+    }
+}
+
+fn call_global(env: NapiEnv, name: &str, value: NapiValue) -> Result<NapiValue> {
+    let global = napi::get_global(env)?;
+    let function = napi::get_named_property(env, global, name)?;
+    napi::call_function(env, global, function, &[value])
+}
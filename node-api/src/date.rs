@@ -0,0 +1,158 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+use napi;
+use napi::{NapiEnv, NapiValue};
+use napi_value::{FromNapiValue, IntoNapiValue, range_error};
+use handle::JsValue;
+use env::Env;
+use error::{Result, NapiError, NapiErrorType};
+
+/// The largest distance from the Unix epoch a JavaScript `Date` can hold,
+/// in milliseconds.
+const MAX_TIME: f64 = 8.64e15;
+
+/// A JavaScript `Date`.
+///
+/// Dates are stored as milliseconds since the Unix epoch. Invalid dates,
+/// such as `new Date("nonsense")`, hold `NaN` and are rejected when
+/// converted to Rust times.
+#[derive(Debug, Clone, Copy)]
+pub struct JsDate<'a> {
+    value: JsValue<'a>,
+}
+
+impl<'a> JsDate<'a> {
+    pub fn from_value(value: JsValue<'a>) -> Result<JsDate<'a>> {
+        check_date(value.env().raw(), value.raw())?;
+        Ok(JsDate { value: value })
+    }
+
+    /// Creates a date from milliseconds since the Unix epoch. Times that
+    /// would make an invalid date are rejected with a `RangeError`.
+    pub fn new(env: Env<'a>, time: f64) -> Result<JsDate<'a>> {
+        let raw = create_date(env.raw(), time)?;
+        Ok(JsDate { value: unsafe { env.value(raw) } })
+    }
+
+    pub fn from_system_time(env: Env<'a>, time: SystemTime) -> Result<JsDate<'a>> {
+        let raw = time.into_napi_value(env.raw())?;
+        Ok(JsDate { value: unsafe { env.value(raw) } })
+    }
+
+    pub fn value(&self) -> JsValue<'a> {
+        self.value
+    }
+
+    /// Returns the milliseconds since the Unix epoch, which are `NaN` for
+    /// invalid dates.
+    pub fn time(&self) -> Result<f64> {
+        napi::get_date_value(self.value.env().raw(), self.value.raw())
+    }
+
+    pub fn to_system_time(&self) -> Result<SystemTime> {
+        SystemTime::from_napi_value(self.value.env(), self.value)
+    }
+}
+
+impl<'a> FromNapiValue<'a> for JsDate<'a> {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        JsDate::from_value(value)
+    }
+}
+
+impl<'a> IntoNapiValue for JsDate<'a> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value.raw())
+    }
+}
+
+/// Dates have millisecond precision, so anything finer is truncated.
+impl<'a> FromNapiValue<'a> for SystemTime {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<SystemTime> {
+        let time = get_time(env.raw(), value.raw())?;
+        let since_epoch = Duration::from_millis(time.abs() as u64);
+        let result = if time < 0.0 {
+            UNIX_EPOCH.checked_sub(since_epoch)
+        } else {
+            UNIX_EPOCH.checked_add(since_epoch)
+        };
+        result.ok_or_else(|| range_error(format!("{} is out of range for SystemTime", time)))
+    }
+}
+
+impl IntoNapiValue for SystemTime {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let time = match self.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => millis(since_epoch),
+            Err(err) => -millis(err.duration()),
+        };
+        create_date(env, time.trunc())
+    }
+}
+
+/// `DateTime<Utc>` and `NaiveDateTime` require the `chrono` feature. Naive
+/// times are taken to be in UTC.
+#[cfg(feature = "chrono")]
+impl<'a> FromNapiValue<'a> for DateTime<Utc> {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<DateTime<Utc>> {
+        let time = get_time(env.raw(), value.raw())?;
+        Utc.timestamp_millis_opt(time as i64)
+            .single()
+            .ok_or_else(|| range_error(format!("{} is out of range for DateTime", time)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl IntoNapiValue for DateTime<Utc> {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        create_date(env, self.timestamp_millis() as f64)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'a> FromNapiValue<'a> for NaiveDateTime {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<NaiveDateTime> {
+        DateTime::<Utc>::from_napi_value(env, value).map(|time| time.naive_utc())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl IntoNapiValue for NaiveDateTime {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        Utc.from_utc_datetime(&self).into_napi_value(env)
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 / 1_000_000.0
+}
+
+fn check_date(env: NapiEnv, value: NapiValue) -> Result<()> {
+    if napi::is_date(env, value)? {
+        Ok(())
+    } else {
+        Err(NapiError::new(NapiErrorType::DateExpected,
+                           format!("expected a Date, but found {:?}", napi::type_of(env, value)?)))
+    }
+}
+
+/// Reads the milliseconds since the Unix epoch of a valid date.
+fn get_time(env: NapiEnv, value: NapiValue) -> Result<f64> {
+    check_date(env, value)?;
+    let time = napi::get_date_value(env, value)?;
+    if time.is_nan() {
+        return Err(range_error("invalid Date".to_string()));
+    }
+    Ok(time)
+}
+
+/// Creates a date, rejecting times that JavaScript would turn into an
+/// invalid date.
+fn create_date(env: NapiEnv, time: f64) -> Result<NapiValue> {
+    if time.is_nan() || time.abs() > MAX_TIME {
+        return Err(range_error(format!("{} is out of range for a Date", time)));
+    }
+    napi::create_date(env, time)
+}
@@ -0,0 +1,522 @@
+use std::marker::PhantomData;
+
+use napi;
+use napi::{NapiEnv, NapiRef, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue, IntoNapiValues};
+use function::JsFunction;
+use class::ClassProperty;
+use handle::JsValue;
+use scope::{HandleScope, EscapableHandleScope};
+use error::Result;
+
+/// The environment of a native call.
+///
+/// `Env<'a>` hands out `JsValue<'a>`s, so every value created through it is
+/// bound to the scope it was created in. Open a `HandleScope` to get a
+/// shorter-lived `Env` and a `Reference` to keep a value beyond any scope.
+///
+/// Its methods mirror the free functions in `node_api::napi`. Like the
+/// values it hands out, an `Env` cannot leave the JavaScript thread.
+#[derive(Debug, Clone, Copy)]
+pub struct Env<'a> {
+    raw: NapiEnv,
+    _scope: PhantomData<(&'a (), *mut ())>,
+}
+
+impl<'a> Env<'a> {
+    /// Wraps a raw environment handle.
+    ///
+    /// # Safety
+    ///
+    /// The caller chooses `'a` and has to make sure it does not outlive the
+    /// native call the handle was passed to. Prefer `Env::scoped`, which picks
+    /// the lifetime for you.
+    pub unsafe fn from_raw(raw: NapiEnv) -> Env<'a> {
+        Env {
+            raw: raw,
+            _scope: PhantomData,
+        }
+    }
+
+    /// Runs `f` with an `Env` whose values cannot escape the closure.
+    pub fn scoped<F, R>(raw: NapiEnv, f: F) -> R
+        where F: for<'s> FnOnce(Env<'s>) -> R
+    {
+        f(unsafe { Env::from_raw(raw) })
+    }
+
+    pub fn raw(&self) -> NapiEnv {
+        self.raw
+    }
+
+    /// Binds a raw value handle to this environment's scope.
+    ///
+    /// # Safety
+    ///
+    /// The handle has to be valid for `'a`, e.g. an argument of the current
+    /// call or a value created in this scope.
+    pub unsafe fn value(&self, raw: NapiValue) -> JsValue<'a> {
+        JsValue::from_raw(*self, raw)
+    }
+
+    /// Runs `f` inside a new handle scope. Values created through the scope's
+    /// `Env` are released when `f` returns.
+    ///
+    /// This `Env` is borrowed for as long as the scope is open, so it cannot
+    /// create values that would be released with the scope:
+    ///
+    /// ```compile_fail
+    /// # use node_api::{Env, JsValue};
+    /// # use node_api::error::Result;
+    /// fn leak<'a>(mut env: Env<'a>) -> Result<JsValue<'a>> {
+    ///     let mut kept = None;
+    ///     env.handle_scope(|_| {
+    ///         kept = Some(env.create_string_utf8("x")?);
+    ///         Ok(())
+    ///     })?;
+    ///     Ok(kept.unwrap())
+    /// }
+    /// ```
+    pub fn handle_scope<F, R>(&mut self, f: F) -> Result<R>
+        where F: for<'s> FnOnce(&'s HandleScope<'s, 'a>) -> Result<R>
+    {
+        let scope = HandleScope::open(self)?;
+        let result = f(&scope);
+        scope.close()?;
+        result
+    }
+
+    /// Runs `f` inside a new escapable handle scope and moves the value it
+    /// returns out into this scope.
+    pub fn escapable_handle_scope<F>(&mut self, f: F) -> Result<JsValue<'a>>
+        where F: for<'s> FnOnce(&'s EscapableHandleScope<'s, 'a>) -> Result<JsValue<'s>>
+    {
+        let scope = EscapableHandleScope::open(self)?;
+        let escaped = {
+            let value = f(&scope)?;
+            scope.escape(value)?
+        };
+        scope.close()?;
+        Ok(escaped)
+    }
+
+    unsafe fn wrap_value(&self, raw: NapiValue) -> JsValue<'a> {
+        JsValue::from_raw(*self, raw)
+    }
+
+    /// Converts a Rust value into a JavaScript value.
+    #[track_caller]
+    pub fn to_js<T>(&self, value: T) -> Result<JsValue<'a>>
+        where T: IntoNapiValue
+    {
+        let raw = value.into_napi_value(self.raw)?;
+        Ok(unsafe { self.wrap_value(raw) })
+    }
+
+    /// Converts a JavaScript value into a Rust value.
+    #[track_caller]
+    pub fn from_js<T>(&self, value: JsValue<'a>) -> Result<T>
+        where T: FromNapiValue<'a>
+    {
+        T::from_napi_value(*self, value)
+    }
+
+    #[track_caller]
+    pub fn get_undefined(&self) -> Result<JsValue<'a>> {
+        napi::get_undefined(self.raw).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn get_null(&self) -> Result<JsValue<'a>> {
+        napi::get_null(self.raw).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn get_global(&self) -> Result<JsValue<'a>> {
+        napi::get_global(self.raw).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn get_boolean(&self, value: bool) -> Result<JsValue<'a>> {
+        napi::get_boolean(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_object(&self) -> Result<JsValue<'a>> {
+        napi::create_object(self.raw).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_array(&self) -> Result<JsValue<'a>> {
+        napi::create_array(self.raw).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn array_with_length(&self, size: usize) -> Result<JsValue<'a>> {
+        napi::array_with_length(self.raw, size).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_double(&self, value: f64) -> Result<JsValue<'a>> {
+        napi::create_double(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_i32(&self, value: i32) -> Result<JsValue<'a>> {
+        napi::create_i32(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_u32(&self, value: u32) -> Result<JsValue<'a>> {
+        napi::create_u32(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_i64(&self, value: i64) -> Result<JsValue<'a>> {
+        napi::create_i64(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_bigint_i64(&self, value: i64) -> Result<JsValue<'a>> {
+        napi::create_bigint_i64(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_bigint_u64(&self, value: u64) -> Result<JsValue<'a>> {
+        napi::create_bigint_u64(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_bigint_words(&self, negative: bool, words: &[u64]) -> Result<JsValue<'a>> {
+        napi::create_bigint_words(self.raw, negative, words).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_string_utf8<T>(&self, value: T) -> Result<JsValue<'a>>
+        where T: AsRef<str>
+    {
+        napi::create_string_utf8(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_string_latin1(&self, value: &[u8]) -> Result<JsValue<'a>> {
+        napi::create_string_latin1(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_string_utf16(&self, value: &[u16]) -> Result<JsValue<'a>> {
+        napi::create_string_utf16(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_symbol(&self, description: Option<&str>) -> Result<JsValue<'a>> {
+        napi::create_symbol(self.raw, description).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    /// Creates a function that calls `f`. `f` gets the `Env` of each call,
+    /// so the values it creates do not outlive the call.
+    #[track_caller]
+    pub fn create_function<F, T, R>(&self, name: &str, f: F) -> Result<JsValue<'a>>
+        where F: for<'s> Fn(Env<'s>, JsValue<'s>, T) -> R + 'static,
+              T: for<'s> FromNapiValues<'s>,
+              R: IntoNapiValue
+    {
+        napi::create_function(self.raw, name, f).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    /// Like `create_function`, for closures that keep state between calls.
+    #[track_caller]
+    pub fn create_function_mut<F, T, R>(&self, name: &str, f: F) -> Result<JsValue<'a>>
+        where F: for<'s> FnMut(Env<'s>, JsValue<'s>, T) -> R + 'static,
+              T: for<'s> FromNapiValues<'s>,
+              R: IntoNapiValue
+    {
+        napi::create_function_mut(self.raw, name, f).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    /// Defines a class whose constructor calls `constructor` with the new
+    /// instance as `this`, with `properties` on its prototype or, if they are
+    /// static, on the constructor.
+    #[track_caller]
+    pub fn define_class<F>(&self, name: &str, constructor: F, properties: Vec<ClassProperty<'a>>) -> Result<JsValue<'a>>
+        where F: for<'s> Fn(Env<'s>, JsValue<'s>, &[JsValue<'s>]) -> Result<JsValue<'s>> + 'static
+    {
+        let descriptors = properties.into_iter().map(ClassProperty::into_descriptor).collect();
+        napi::define_class(self.raw, name, constructor, descriptors).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_external<T>(&self, value: Box<T>) -> Result<JsValue<'a>> {
+        napi::create_external(self.raw, value).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    /// The data of an external created with `create_external`.
+    ///
+    /// # Safety
+    ///
+    /// `value` has to be an external created from a `Box<T>`.
+    #[track_caller]
+    pub unsafe fn get_value_external<T>(&self, value: JsValue<'a>) -> Result<&'a T> {
+        napi::get_value_external(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn type_of(&self, value: JsValue<'a>) -> Result<NapiValueType> {
+        napi::type_of(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_double(&self, value: JsValue<'a>) -> Result<f64> {
+        napi::get_value_double(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_int32(&self, value: JsValue<'a>) -> Result<i32> {
+        napi::get_value_int32(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_uint32(&self, value: JsValue<'a>) -> Result<u32> {
+        napi::get_value_uint32(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_int64(&self, value: JsValue<'a>) -> Result<i64> {
+        napi::get_value_int64(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_bigint_i64(&self, value: JsValue<'a>) -> Result<(i64, bool)> {
+        napi::get_value_bigint_i64(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_bigint_u64(&self, value: JsValue<'a>) -> Result<(u64, bool)> {
+        napi::get_value_bigint_u64(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_bigint_words(&self, value: JsValue<'a>) -> Result<(bool, Vec<u64>)> {
+        napi::get_value_bigint_words(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn coerce_to_bool(&self, value: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::coerce_to_bool(self.raw, value.raw()).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn coerce_to_number(&self, value: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::coerce_to_number(self.raw, value.raw()).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn coerce_to_object(&self, value: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::coerce_to_object(self.raw, value.raw()).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn coerce_to_string(&self, value: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::coerce_to_string(self.raw, value.raw()).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn get_value_bool(&self, value: JsValue<'a>) -> Result<bool> {
+        napi::get_value_bool(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_string_utf8(&self, value: JsValue<'a>) -> Result<String> {
+        napi::get_value_string_utf8(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_string_latin1(&self, value: JsValue<'a>) -> Result<Vec<u8>> {
+        napi::get_value_string_latin1(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_value_string_utf16(&self, value: JsValue<'a>) -> Result<Vec<u16>> {
+        napi::get_value_string_utf16(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn set_named_property(&self,
+                              object: JsValue<'a>,
+                              name: &str,
+                              value: JsValue<'a>)
+                              -> Result<()> {
+        napi::set_named_property(self.raw, object.raw(), name, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_named_property(&self, object: JsValue<'a>, name: &str) -> Result<JsValue<'a>> {
+        napi::get_named_property(self.raw, object.raw(), name)
+            .map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn has_named_property(&self, object: JsValue<'a>, name: &str) -> Result<bool> {
+        napi::has_named_property(self.raw, object.raw(), name)
+    }
+
+    #[track_caller]
+    pub fn set_property(&self,
+                        object: JsValue<'a>,
+                        key: JsValue<'a>,
+                        value: JsValue<'a>)
+                        -> Result<()> {
+        napi::set_property(self.raw, object.raw(), key.raw(), value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_property(&self, object: JsValue<'a>, key: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::get_property(self.raw, object.raw(), key.raw())
+            .map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn has_property(&self, object: JsValue<'a>, key: JsValue<'a>) -> Result<bool> {
+        napi::has_property(self.raw, object.raw(), key.raw())
+    }
+
+    #[track_caller]
+    pub fn get_prototype(&self, object: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::get_prototype(self.raw, object.raw()).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn get_property_names(&self, object: JsValue<'a>) -> Result<JsValue<'a>> {
+        napi::get_property_names(self.raw, object.raw()).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn set_element(&self, array: JsValue<'a>, index: usize, value: JsValue<'a>) -> Result<()> {
+        napi::set_element(self.raw, array.raw(), index, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_element(&self, array: JsValue<'a>, index: usize) -> Result<JsValue<'a>> {
+        napi::get_element(self.raw, array.raw(), index).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn has_element(&self, array: JsValue<'a>, index: usize) -> Result<bool> {
+        napi::has_element(self.raw, array.raw(), index)
+    }
+
+    #[track_caller]
+    pub fn is_array(&self, value: JsValue<'a>) -> Result<bool> {
+        napi::is_array(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_array_length(&self, value: JsValue<'a>) -> Result<usize> {
+        napi::get_array_length(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn create_date(&self, time: f64) -> Result<JsValue<'a>> {
+        napi::create_date(self.raw, time).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn is_date(&self, value: JsValue<'a>) -> Result<bool> {
+        napi::is_date(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn get_date_value(&self, value: JsValue<'a>) -> Result<f64> {
+        napi::get_date_value(self.raw, value.raw())
+    }
+
+    #[track_caller]
+    pub fn strict_equals(&self, lhs: JsValue<'a>, rhs: JsValue<'a>) -> Result<bool> {
+        napi::strict_equals(self.raw, lhs.raw(), rhs.raw())
+    }
+
+    #[track_caller]
+    pub fn instance_of(&self, object: JsValue<'a>, constructor: JsValue<'a>) -> Result<bool> {
+        napi::instance_of(self.raw, object.raw(), constructor.raw())
+    }
+
+    #[track_caller]
+    pub fn call_function(&self,
+                         recv: JsValue<'a>,
+                         func: JsValue<'a>,
+                         args: &[JsValue<'a>])
+                         -> Result<JsValue<'a>> {
+        let args: Vec<NapiValue> = args.iter().map(|arg| arg.raw()).collect();
+        napi::call_function(self.raw, recv.raw(), func.raw(), &args)
+            .map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    /// Looks up the method `name` on `object` and calls it with `object` as
+    /// `this`.
+    #[track_caller]
+    pub fn call_method<A, R>(&self, object: JsValue<'a>, name: &str, args: A) -> Result<R>
+        where A: IntoNapiValues,
+              R: FromNapiValue<'a>
+    {
+        let method = JsFunction::from_value(self.get_named_property(object, name)?)?;
+        method.call(object, args)
+    }
+
+    #[track_caller]
+    pub fn wrap<T>(&self, object: JsValue<'a>, native_object: Box<T>) -> Result<NapiRef> {
+        napi::wrap(self.raw, object.raw(), native_object)
+    }
+
+    #[track_caller]
+    pub fn create_error(&self, code: Option<&str>, msg: &str) -> Result<JsValue<'a>> {
+        napi::create_error(self.raw, code, msg).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_type_error(&self, code: Option<&str>, msg: &str) -> Result<JsValue<'a>> {
+        napi::create_type_error(self.raw, code, msg).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn create_range_error(&self, code: Option<&str>, msg: &str) -> Result<JsValue<'a>> {
+        napi::create_range_error(self.raw, code, msg).map(|raw| unsafe { self.wrap_value(raw) })
+    }
+
+    #[track_caller]
+    pub fn throw(&self, error: JsValue<'a>) -> Result<()> {
+        napi::throw(self.raw, error.raw())
+    }
+
+    #[track_caller]
+    pub fn throw_error(&self, msg: &str) -> Result<()> {
+        napi::throw_error(self.raw, msg)
+    }
+
+    #[track_caller]
+    pub fn throw_type_error(&self, msg: &str) -> Result<()> {
+        napi::throw_type_error(self.raw, msg)
+    }
+
+    #[track_caller]
+    pub fn throw_range_error(&self, msg: &str) -> Result<()> {
+        napi::throw_range_error(self.raw, msg)
+    }
+
+    #[track_caller]
+    pub fn is_error(&self, value: JsValue<'a>) -> Result<bool> {
+        napi::is_error(self.raw, value.raw())
+    }
+
+    /// Runs `execute` on the libuv thread pool and hands its result to
+    /// `complete` back on the JavaScript thread.
+    #[track_caller]
+    pub fn queue_async_work<E, C, T>(&self, name: &str, execute: E, complete: C) -> Result<()>
+        where E: FnOnce() -> T + Send + 'static,
+              C: for<'s> FnOnce(Env<'s>, Result<T>) + 'static,
+              T: Send + 'static
+    {
+        napi::queue_async_work(self.raw, name, execute, move |env, result| {
+            Env::scoped(env, move |env| complete(env, result))
+        })
+    }
+}
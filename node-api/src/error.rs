@@ -1,15 +1,20 @@
-use std::{result, ffi, string};
+use std::{result, ffi, string, str, fmt, io, num, error, ptr, os};
+use std::cell::{Cell, RefCell};
 use std::ffi::CStr;
+use std::panic::Location;
+use std::sync::Arc;
 
 use node_api_sys::*;
+use napi;
+use napi::{NapiEnv, NapiRef, NapiValue};
+use env::Env;
+use handle::JsValue;
+use js_error::JsErrorKind;
+use napi_value::range_error;
 pub type Result<T> = result::Result<T, NapiError>;
 
 fn make_generic_napi_error(message: &str) -> NapiError {
-    NapiError {
-        error_message: message.to_string(),
-        engine_error_code: 0,
-        error_code: NapiErrorType::GenericFailure,
-    }
+    NapiError::new(NapiErrorType::GenericFailure, message)
 }
 
 #[derive(Debug, Clone)]
@@ -17,22 +22,264 @@ pub struct NapiError {
     pub error_message: String,
     pub engine_error_code: u32,
     pub error_code: NapiErrorType,
+    /// The value thrown by JavaScript code, if this error was caused by an
+    /// exception. The exception has been cleared, so JavaScript can continue
+    /// to run; returning the error from a native function rethrows it.
+    pub exception: Option<Box<JsException>>,
+    /// The N-API function that failed, such as `"napi_get_value_double"`.
+    pub operation: Option<&'static str>,
+    /// Where in Rust code the failing call was made.
+    pub location: Option<&'static Location<'static>>,
+    /// The constructor of the JavaScript error this error is thrown as,
+    /// unless it was caused by an exception.
+    pub kind: JsErrorKind,
+    /// The `code` property of the JavaScript error this error is thrown as.
+    pub code: Option<String>,
+    /// An error object created by native code, such as by
+    /// `JsError::into_napi_error`, which is thrown instead of a new error.
+    /// Unlike `exception`, it has never been thrown.
+    error_object: Option<Arc<KeptValue>>,
 }
 
-impl From<napi_extended_error_info> for NapiError {
-    fn from(error: napi_extended_error_info) -> Self {
+/// A value thrown by JavaScript code.
+///
+/// The `name`, `message` and `stack` of `Error` objects are read when the
+/// exception is caught, so they remain available after the handle to the
+/// value has gone out of scope. For thrown primitives, `message` is the
+/// value converted to a string.
+///
+/// The thrown value itself is kept alive by a reference and rethrown as it
+/// was, as long as the environment it was thrown in is alive and the error is
+/// rethrown on its thread. Otherwise an `Error` with the same name, message
+/// and stack is thrown instead.
+#[derive(Debug, Clone)]
+pub struct JsException {
+    pub name: Option<String>,
+    pub message: Option<String>,
+    pub stack: Option<String>,
+    thrown: Option<Arc<KeptValue>>,
+}
+
+impl JsException {
+    pub(crate) fn new(env: NapiEnv,
+                      value: NapiValue,
+                      name: Option<String>,
+                      message: Option<String>,
+                      stack: Option<String>) -> JsException {
+        JsException {
+            name: name,
+            message: message,
+            stack: stack,
+            thrown: KeptValue::new(env, value).map(Arc::new),
+        }
+    }
+
+    /// The thrown value, if it can still be used in `env`.
+    pub fn value<'a>(&self, env: Env<'a>) -> Option<JsValue<'a>> {
+        let raw = self.thrown.as_ref()?.get(env.raw())?;
+        Some(unsafe { JsValue::from_raw(env, raw) })
+    }
+
+    /// The value to throw again: the thrown value if it can still be used in
+    /// `env`, or else an `Error` recreated from its name, message and stack.
+    pub(crate) fn rethrown(&self, env: NapiEnv) -> Result<NapiValue> {
+        if let Some(value) = self.thrown.as_ref().and_then(|thrown| thrown.get(env)) {
+            return Ok(value);
+        }
+        let message = self.message.as_ref().map_or("", |message| &message[..]);
+        let error = napi::create_error(env, None, message)?;
+        if let Some(ref name) = self.name {
+            let name = napi::create_string_utf8(env, name)?;
+            napi::set_named_property(env, error, "name", name)?;
+        }
+        if let Some(ref stack) = self.stack {
+            let stack = napi::create_string_utf8(env, stack)?;
+            napi::set_named_property(env, error, "stack", stack)?;
+        }
+        Ok(error)
+    }
+}
+
+thread_local! {
+    /// The environments alive on this thread, each with the generation it
+    /// was first seen in, so that an environment created at the address of
+    /// a destroyed one is not mistaken for it.
+    static LIVE_ENVS: RefCell<Vec<(NapiEnv, u64)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A strong reference to a value kept by an error, either a thrown value or
+/// an error object created by native code. The value is held in an array,
+/// since primitives cannot be referenced directly.
+///
+/// Errors are `Send` and `'static`, so they can outlive the environment the
+/// value was thrown in or move to another thread. The reference is only used
+/// and deleted while its environment is alive on the current thread, and is
+/// left to the environment to clean up otherwise.
+#[derive(Debug)]
+struct KeptValue {
+    env: NapiEnv,
+    generation: u64,
+    raw: NapiRef,
+}
+
+// The reference is only touched on the thread of its environment, which
+// `generation_of` checks through a thread local.
+unsafe impl Send for KeptValue {}
+unsafe impl Sync for KeptValue {}
+
+impl KeptValue {
+    fn new(env: NapiEnv, value: NapiValue) -> Option<KeptValue> {
+        let generation = track_env(env)?;
+        let mut holder: NapiValue = 0;
+        let mut raw: NapiRef = ptr::null_mut();
         unsafe {
-            Self {
-                error_message: CStr::from_ptr(error.error_message)
-                    .to_string_lossy()
-                    .into_owned(),
-                engine_error_code: error.engine_error_code,
-                error_code: NapiErrorType::from(error.error_code),
+            if napi_create_array_with_length(env, 1, &mut holder) != napi_status::napi_ok ||
+               napi_set_element(env, holder, 0, value) != napi_status::napi_ok ||
+               napi_create_reference(env, holder, 1, &mut raw) != napi_status::napi_ok {
+                return None;
             }
         }
+        Some(KeptValue {
+            env: env,
+            generation: generation,
+            raw: raw,
+        })
+    }
+
+    fn get(&self, env: NapiEnv) -> Option<NapiValue> {
+        if env != self.env || generation_of(env) != Some(self.generation) {
+            return None;
+        }
+        let mut holder: NapiValue = 0;
+        let mut value: NapiValue = 0;
+        unsafe {
+            if napi_get_reference_value(env, self.raw, &mut holder) != napi_status::napi_ok ||
+               napi_get_element(env, holder, 0, &mut value) != napi_status::napi_ok {
+                return None;
+            }
+        }
+        Some(value)
+    }
+}
+
+impl Drop for KeptValue {
+    fn drop(&mut self) {
+        if generation_of(self.env) == Some(self.generation) {
+            unsafe { napi_delete_reference(self.env, self.raw) };
+        }
+    }
+}
+
+/// The generation of `env` if it is alive on this thread.
+fn generation_of(env: NapiEnv) -> Option<u64> {
+    LIVE_ENVS.try_with(|envs| {
+        envs.borrow().iter().find(|&&(live, _)| live == env).map(|&(_, generation)| generation)
+    }).ok()?
+}
+
+/// Starts tracking `env`, which has to be alive on this thread, until it is
+/// torn down.
+fn track_env(env: NapiEnv) -> Option<u64> {
+    if let Some(generation) = generation_of(env) {
+        return Some(generation);
+    }
+    let status = unsafe { napi_add_env_cleanup_hook(env, Some(forget_env), env as *mut os::raw::c_void) };
+    if status != napi_status::napi_ok {
+        return None;
+    }
+    let generation = NEXT_GENERATION.with(|next| {
+        let generation = next.get();
+        next.set(generation + 1);
+        generation
+    });
+    LIVE_ENVS.with(|envs| envs.borrow_mut().push((env, generation)));
+    Some(generation)
+}
+
+unsafe extern "C" fn forget_env(env: *mut os::raw::c_void) {
+    let env = env as NapiEnv;
+    let _ = LIVE_ENVS.try_with(|envs| envs.borrow_mut().retain(|&(live, _)| live != env));
+}
+
+impl NapiError {
+    pub fn new<S>(error_code: NapiErrorType, message: S) -> NapiError
+        where S: Into<String>
+    {
+        NapiError {
+            error_message: message.into(),
+            engine_error_code: 0,
+            error_code: error_code,
+            exception: None,
+            operation: None,
+            location: None,
+            kind: JsErrorKind::Error,
+            code: None,
+            error_object: None,
+        }
     }
 }
 
+impl NapiError {
+    /// Keeps `value`, an error object created in `env`, to be thrown in place
+    /// of a new error.
+    pub(crate) fn with_error_object(mut self, env: NapiEnv, value: NapiValue) -> NapiError {
+        self.error_object = KeptValue::new(env, value).map(Arc::new);
+        self
+    }
+
+    /// The error object to throw in `env`: the one created by native code if
+    /// it can still be used there, or else a new error of the same kind with
+    /// the same message and code.
+    pub(crate) fn error_object(&self, env: NapiEnv) -> Result<NapiValue> {
+        if let Some(value) = self.error_object.as_ref().and_then(|object| object.get(env)) {
+            return Ok(value);
+        }
+        let code = self.code.as_deref();
+        match self.kind {
+            JsErrorKind::Error => napi::create_error(env, code, &self.error_message),
+            JsErrorKind::TypeError => napi::create_type_error(env, code, &self.error_message),
+            JsErrorKind::RangeError => napi::create_range_error(env, code, &self.error_message),
+        }
+    }
+}
+
+impl From<napi_extended_error_info> for NapiError {
+    fn from(error: napi_extended_error_info) -> Self {
+        let error_message = if error.error_message.is_null() {
+            format!("N-API call failed with status {}", error.error_code.0)
+        } else {
+            unsafe { CStr::from_ptr(error.error_message) }.to_string_lossy().into_owned()
+        };
+        Self {
+            error_message: error_message,
+            engine_error_code: error.engine_error_code,
+            error_code: NapiErrorType::from(error.error_code),
+            exception: None,
+            operation: None,
+            location: None,
+            kind: JsErrorKind::Error,
+            code: None,
+            error_object: None,
+        }
+    }
+}
+
+impl fmt::Display for NapiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(operation) = self.operation {
+            write!(f, "{} failed: ", operation)?;
+        }
+        f.write_str(&self.error_message)?;
+        if let Some(location) = self.location {
+            write!(f, " (at {})", location)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for NapiError {}
+
 impl From<ffi::NulError> for NapiError {
     fn from(_: ffi::NulError) -> Self {
         make_generic_napi_error("string must not contain 0 byte")
@@ -45,6 +292,48 @@ impl From<string::FromUtf8Error> for NapiError {
     }
 }
 
+impl From<str::Utf8Error> for NapiError {
+    fn from(err: str::Utf8Error) -> Self {
+        make_generic_napi_error(&err.to_string())
+    }
+}
+
+impl From<string::FromUtf16Error> for NapiError {
+    fn from(_: string::FromUtf16Error) -> Self {
+        NapiError::new(NapiErrorType::InvalidArg, "string contains an unpaired surrogate")
+    }
+}
+
+impl From<num::ParseIntError> for NapiError {
+    fn from(err: num::ParseIntError) -> Self {
+        NapiError::new(NapiErrorType::InvalidArg, err.to_string())
+    }
+}
+
+impl From<num::ParseFloatError> for NapiError {
+    fn from(err: num::ParseFloatError) -> Self {
+        NapiError::new(NapiErrorType::InvalidArg, err.to_string())
+    }
+}
+
+impl From<num::TryFromIntError> for NapiError {
+    fn from(err: num::TryFromIntError) -> Self {
+        range_error(err.to_string())
+    }
+}
+
+impl From<io::Error> for NapiError {
+    fn from(err: io::Error) -> Self {
+        make_generic_napi_error(&err.to_string())
+    }
+}
+
+impl From<fmt::Error> for NapiError {
+    fn from(err: fmt::Error) -> Self {
+        make_generic_napi_error(&err.to_string())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum NapiErrorType {
     InvalidArg,
@@ -58,12 +347,36 @@ pub enum NapiErrorType {
     GenericFailure,
     PendingException,
     Cancelled,
-    StatusLast,
+    EscapeCalledTwice,
+    HandleScopeMismatch,
+    CallbackScopeMismatch,
+    /// A thread-safe function's queue is full.
+    QueueFull,
+    /// A thread-safe function is being released or the environment is
+    /// shutting down.
+    Closing,
+    BigIntExpected,
+    DateExpected,
+    ArrayBufferExpected,
+    DetachableArrayBufferExpected,
+    WouldDeadlock,
+    NoExternalBuffersAllowed,
+    /// JavaScript cannot run, for example while the environment is being
+    /// torn down.
+    CannotRunJs,
+    /// A status this version of the crate does not know about, with its raw
+    /// value.
+    Unknown(u32),
 }
 
 impl From<napi_status> for NapiErrorType {
     fn from(s: napi_status) -> Self {
         match s {
+            // only failed calls are turned into errors
+            napi_status::napi_ok => {
+                debug_assert!(false, "napi_ok is not an error status");
+                NapiErrorType::GenericFailure
+            }
             napi_status::napi_invalid_arg => NapiErrorType::InvalidArg,
             napi_status::napi_object_expected => NapiErrorType::ObjectExpected,
             napi_status::napi_string_expected => NapiErrorType::StringExpected,
@@ -75,7 +388,21 @@ impl From<napi_status> for NapiErrorType {
             napi_status::napi_generic_failure => NapiErrorType::GenericFailure,
             napi_status::napi_pending_exception => NapiErrorType::PendingException,
             napi_status::napi_cancelled => NapiErrorType::Cancelled,
-            _ => NapiErrorType::GenericFailure,
+            napi_status::napi_escape_called_twice => NapiErrorType::EscapeCalledTwice,
+            napi_status::napi_handle_scope_mismatch => NapiErrorType::HandleScopeMismatch,
+            napi_status::napi_callback_scope_mismatch => NapiErrorType::CallbackScopeMismatch,
+            napi_status::napi_queue_full => NapiErrorType::QueueFull,
+            napi_status::napi_closing => NapiErrorType::Closing,
+            napi_status::napi_bigint_expected => NapiErrorType::BigIntExpected,
+            napi_status::napi_date_expected => NapiErrorType::DateExpected,
+            napi_status::napi_arraybuffer_expected => NapiErrorType::ArrayBufferExpected,
+            napi_status::napi_detachable_arraybuffer_expected => {
+                NapiErrorType::DetachableArrayBufferExpected
+            }
+            napi_status::napi_would_deadlock => NapiErrorType::WouldDeadlock,
+            napi_status::napi_no_external_buffers_allowed => NapiErrorType::NoExternalBuffersAllowed,
+            napi_status::napi_cannot_run_js => NapiErrorType::CannotRunJs,
+            napi_status(status) => NapiErrorType::Unknown(status),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,280 @@
+//! Support code for the `#[napi]` attribute and the conversion derives.
+
+use std::sync::Mutex;
+
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue};
+use handle::JsValue;
+use env::Env;
+use module::Exports;
+use fatal;
+use error::{Result, NapiError, NapiErrorType};
+
+/// Creates the value of an export in the given environment.
+pub type CreateExport = fn(NapiEnv) -> Result<NapiValue>;
+
+static EXPORTS: Mutex<Vec<(&'static str, CreateExport)>> = Mutex::new(Vec::new());
+
+/// Adds an export to the module. Called by the static constructors that
+/// `#[napi]` generates, before the module is loaded.
+#[doc(hidden)]
+pub fn register_export(name: &'static str, create: CreateExport) {
+    EXPORTS.lock().unwrap_or_else(|err| err.into_inner()).push((name, create));
+}
+
+/// The exports registered with `#[napi]`.
+#[doc(hidden)]
+pub fn registered() -> Vec<(&'static str, CreateExport)> {
+    EXPORTS.lock().unwrap_or_else(|err| err.into_inner()).clone()
+}
+
+/// What the register function of `napi_module!` returns: `()`, or a
+/// `Result` whose errors are thrown from `require`. To export something other
+/// than the exports object, use `Exports::replace`.
+pub trait ModuleExports {
+    fn into_exports(self, env: NapiEnv, exports: NapiValue) -> Result<NapiValue>;
+}
+
+impl ModuleExports for () {
+    fn into_exports(self, _: NapiEnv, exports: NapiValue) -> Result<NapiValue> {
+        Ok(exports)
+    }
+}
+
+impl<T> ModuleExports for Result<T>
+    where T: ModuleExports
+{
+    fn into_exports(self, env: NapiEnv, exports: NapiValue) -> Result<NapiValue> {
+        self?.into_exports(env, exports)
+    }
+}
+
+/// Initializes a module: attaches the `#[napi]` exports, then runs the
+/// register function. Called by the entry point `napi_module!` generates.
+#[doc(hidden)]
+pub fn register_module<F, R>(env: NapiEnv, exports: NapiValue, register: F) -> NapiValue
+    where F: for<'a, 'b> FnOnce(&'b mut Exports<'a>) -> R,
+          R: ModuleExports
+{
+    let result = fatal::catch_panic(|| {
+        Env::scoped(env, |env| {
+            let mut builder = Exports::new(env, unsafe { env.value(exports) });
+            builder.registered();
+            let value = register(&mut builder);
+            let exports = builder.object().raw();
+            builder.finish()?;
+            value.into_exports(env.raw(), exports)
+        })
+    });
+    match result.unwrap_or_else(|panic| Err(panic.into_error())) {
+        Ok(exports) => exports,
+        Err(err) => {
+            // the pending exception makes `require` throw
+            if let Ok(exception) = err.into_napi_value(env) {
+                let _ = napi::throw(env, exception);
+            }
+            exports
+        }
+    }
+}
+
+/// The arguments of a call, converted one at a time by `#[napi]` functions.
+#[derive(Debug, Clone)]
+pub struct Arguments<'a> {
+    env: Env<'a>,
+    values: Vec<JsValue<'a>>,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn new(env: Env<'a>, values: &[JsValue<'a>]) -> Arguments<'a> {
+        Arguments {
+            env: env,
+            values: values.to_vec(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Converts the argument at `index`. Missing arguments are `undefined`,
+    /// and conversion errors name the parameter they occurred in.
+    pub fn get<T>(&self, index: usize, name: &str) -> Result<T>
+        where T: FromNapiValue<'a>
+    {
+        let value = match self.values.get(index) {
+            Some(&value) => value,
+            None => self.env.get_undefined()?,
+        };
+        T::from_napi_value(self.env, value).map_err(|mut err| {
+            err.error_message = format!("argument {} (`{}`): {}", index, name, err.error_message);
+            err
+        })
+    }
+}
+
+/// Checks that `value` is an object that a struct called `type_name` can be
+/// read from.
+#[doc(hidden)]
+pub fn check_object(_: Env, value: JsValue, type_name: &str) -> Result<()> {
+    match value.type_of()? {
+        NapiValueType::Object | NapiValueType::Function => Ok(()),
+        value_type => {
+            Err(NapiError::new(NapiErrorType::ObjectExpected,
+                               format!("expected an object for `{}`, but found {:?}", type_name, value_type)))
+        }
+    }
+}
+
+/// Checks that `value` is an array of `length` elements for the tuple struct
+/// `type_name`.
+#[doc(hidden)]
+pub fn check_array(env: Env, value: JsValue, type_name: &str, length: usize) -> Result<()> {
+    if !env.is_array(value)? {
+        return Err(NapiError::new(NapiErrorType::ArrayExpected,
+                                  format!("expected an array for `{}`", type_name)));
+    }
+    let actual = env.get_array_length(value)?;
+    if actual != length {
+        return Err(NapiError::new(NapiErrorType::InvalidArg,
+                                  format!("expected {} elements for `{}`, got {}", length, type_name, actual)));
+    }
+    Ok(())
+}
+
+/// Checks that `value` is `undefined` or `null` for the unit struct
+/// `type_name`.
+#[doc(hidden)]
+pub fn check_unit(_: Env, value: JsValue, type_name: &str) -> Result<()> {
+    match value.type_of()? {
+        NapiValueType::Undefined | NapiValueType::Null => Ok(()),
+        value_type => {
+            Err(NapiError::new(NapiErrorType::InvalidArg,
+                               format!("expected undefined or null for `{}`, but found {:?}", type_name, value_type)))
+        }
+    }
+}
+
+/// Converts the property `name` of `object`, naming it in errors.
+#[doc(hidden)]
+pub fn get_field<'a, T>(env: Env<'a>, object: JsValue<'a>, name: &str) -> Result<T>
+    where T: FromNapiValue<'a>
+{
+    let value = env.get_named_property(object, name)?;
+    T::from_napi_value(env, value).map_err(|err| field_error(err, name))
+}
+
+/// Converts the property `name` of `object`, or returns `None` if it is
+/// `undefined`.
+#[doc(hidden)]
+pub fn get_optional_field<'a, T>(env: Env<'a>, object: JsValue<'a>, name: &str) -> Result<Option<T>>
+    where T: FromNapiValue<'a>
+{
+    let value = env.get_named_property(object, name)?;
+    if value.type_of()? == NapiValueType::Undefined {
+        return Ok(None);
+    }
+    T::from_napi_value(env, value).map(Some).map_err(|err| field_error(err, name))
+}
+
+/// Converts the element at `index` of `array`, naming it in errors.
+#[doc(hidden)]
+pub fn get_element<'a, T>(env: Env<'a>, array: JsValue<'a>, index: usize) -> Result<T>
+    where T: FromNapiValue<'a>
+{
+    let value = env.get_element(array, index)?;
+    T::from_napi_value(env, value).map_err(|mut err| {
+        err.error_message = format!("element {}: {}", index, err.error_message);
+        err
+    })
+}
+
+/// Copies the enumerable properties of `from` onto `to`, for flattened
+/// fields.
+#[doc(hidden)]
+pub fn copy_properties(env: NapiEnv, from: NapiValue, to: NapiValue) -> Result<()> {
+    let names = napi::get_property_names(env, from)?;
+    for index in 0..napi::get_array_length(env, names)? {
+        let name = napi::get_element(env, names, index)?;
+        let value = napi::get_property(env, from, name)?;
+        napi::set_property(env, to, name, value)?;
+    }
+    Ok(())
+}
+
+/// Reads the string naming a variant of the enum `type_name`.
+#[doc(hidden)]
+pub fn get_variant_name(env: Env, value: JsValue, type_name: &str, variants: &[&str]) -> Result<String> {
+    match value.type_of()? {
+        NapiValueType::String => env.get_value_string_utf8(value),
+        value_type => {
+            Err(NapiError::new(NapiErrorType::StringExpected,
+                               format!("expected one of {} for `{}`, but found {:?}",
+                                       list_variants(variants), type_name, value_type)))
+        }
+    }
+}
+
+/// Reads the number selecting a variant of the enum `type_name`.
+#[doc(hidden)]
+pub fn get_variant_number(env: Env, value: JsValue, type_name: &str) -> Result<f64> {
+    match value.type_of()? {
+        NapiValueType::Number => env.get_value_double(value),
+        value_type => {
+            Err(NapiError::new(NapiErrorType::NumberExpected,
+                               format!("expected a number for `{}`, but found {:?}", type_name, value_type)))
+        }
+    }
+}
+
+/// Reads the `tag` property naming the variant of the enum `type_name`.
+#[doc(hidden)]
+pub fn get_variant_tag(env: Env,
+                       object: JsValue,
+                       type_name: &str,
+                       tag: &str,
+                       variants: &[&str])
+                       -> Result<String> {
+    let value = env.get_named_property(object, tag)?;
+    get_variant_name(env, value, type_name, variants).map_err(|err| field_error(err, tag))
+}
+
+#[doc(hidden)]
+pub fn unknown_variant(type_name: &str, variant: &str, variants: &[&str]) -> NapiError {
+    NapiError::new(NapiErrorType::InvalidArg,
+                   format!("unknown variant \"{}\" of `{}`, expected one of {}",
+                           variant, type_name, list_variants(variants)))
+}
+
+#[doc(hidden)]
+pub fn unknown_numeric_variant(type_name: &str, number: f64, variants: &[(i64, &str)]) -> NapiError {
+    let expected: Vec<String> = variants.iter()
+        .map(|&(number, name)| format!("{} ({})", number, name))
+        .collect();
+    NapiError::new(NapiErrorType::InvalidArg,
+                   format!("unknown variant {} of `{}`, expected one of {}",
+                           number, type_name, expected.join(", ")))
+}
+
+#[doc(hidden)]
+pub fn no_matching_variant(type_name: &str, variants: &[&str]) -> NapiError {
+    let expected: Vec<String> = variants.iter().map(|name| format!("`{}`", name)).collect();
+    NapiError::new(NapiErrorType::InvalidArg,
+                   format!("value did not match any variant of `{}`, tried {}",
+                           type_name, expected.join(", ")))
+}
+
+fn list_variants(variants: &[&str]) -> String {
+    let quoted: Vec<String> = variants.iter().map(|name| format!("\"{}\"", name)).collect();
+    quoted.join(", ")
+}
+
+fn field_error(mut err: NapiError, name: &str) -> NapiError {
+    err.error_message = format!("field `{}`: {}", name, err.error_message);
+    err
+}
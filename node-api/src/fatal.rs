@@ -0,0 +1,85 @@
+//! Reporting unrecoverable errors to Node.js.
+//!
+//! Panics must not unwind into Node.js, so they are caught wherever Node.js
+//! calls into Rust. A panic in a native function throws an `Error` naming the
+//! panic, and a panic where nothing can be thrown, such as in a finalizer, is
+//! reported through `napi_fatal_error`, which prints it in Node.js' fatal
+//! error report before aborting.
+//!
+//! The panic message is always reported. Its location is only known to the
+//! panic hook, so it is reported after `install_panic_hook` has been called.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic;
+use std::sync::Once;
+
+use error::{NapiError, NapiErrorType};
+
+pub use napi::fatal_error;
+
+thread_local!(static LAST_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) });
+
+/// Installs a panic hook that remembers where panics happen, so that panics
+/// caught when returning to Node.js are reported with their location. The
+/// hook then runs the previously installed one and does not change how
+/// panics are handled otherwise. Installing the hook more than once has no
+/// effect.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(|location| location.to_string());
+            let _ = LAST_LOCATION.try_with(|last| *last.borrow_mut() = location);
+            previous(info)
+        }));
+    });
+}
+
+/// A panic caught when returning to Node.js.
+#[derive(Debug)]
+pub(crate) struct Panic {
+    message: String,
+    location: Option<String>,
+}
+
+impl Panic {
+    /// An error to throw in place of the panic.
+    pub fn into_error(self) -> NapiError {
+        let message = match self.location {
+            Some(location) => format!("{} (at {})", self.message, location),
+            None => self.message,
+        };
+        NapiError::new(NapiErrorType::GenericFailure, message)
+    }
+
+    /// Reports the panic as a fatal error.
+    pub fn report(self) -> ! {
+        fatal_error(self.location.as_deref(), &self.message)
+    }
+}
+
+/// Runs `f`, catching a panic so that it does not unwind into Node.js.
+pub(crate) fn catch_panic<F, R>(f: F) -> Result<R, Panic>
+    where F: FnOnce() -> R
+{
+    let _ = LAST_LOCATION.try_with(|last| last.borrow_mut().take());
+    // the closures Node.js calls into are not required to be `UnwindSafe`
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).map_err(|payload| {
+        Panic {
+            message: format!("Rust panic: {}", payload_message(&*payload)),
+            location: LAST_LOCATION.try_with(|last| last.borrow_mut().take()).ok().flatten(),
+        }
+    })
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -0,0 +1,59 @@
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue, IntoNapiValues, check_napi_type};
+use handle::JsValue;
+use env::Env;
+use error::Result;
+
+/// A JavaScript function that can be called with Rust values.
+///
+/// Arguments are passed as a tuple of `IntoNapiValue`s and the result is
+/// converted with `FromNapiValue`. If the callee throws, the exception is
+/// cleared and returned as the `exception` of the resulting `NapiError`.
+#[derive(Debug, Clone, Copy)]
+pub struct JsFunction<'a> {
+    value: JsValue<'a>,
+}
+
+impl<'a> JsFunction<'a> {
+    pub fn from_value(value: JsValue<'a>) -> Result<JsFunction<'a>> {
+        check_napi_type(value.env().raw(), NapiValueType::Function, value.raw())?;
+        Ok(JsFunction { value: value })
+    }
+
+    pub fn value(&self) -> JsValue<'a> {
+        self.value
+    }
+
+    pub fn call<A, R>(&self, this: JsValue<'a>, args: A) -> Result<R>
+        where A: IntoNapiValues,
+              R: FromNapiValue<'a>
+    {
+        let env = self.value.env();
+        let args = args.into_napi_values(env.raw())?;
+        let result = napi::call_function(env.raw(), this.raw(), self.value.raw(), &args)?;
+        R::from_napi_value(env, unsafe { env.value(result) })
+    }
+
+    pub fn new_instance<A, R>(&self, args: A) -> Result<R>
+        where A: IntoNapiValues,
+              R: FromNapiValue<'a>
+    {
+        let env = self.value.env();
+        let args = args.into_napi_values(env.raw())?;
+        let result = napi::new_instance(env.raw(), self.value.raw(), &args)?;
+        R::from_napi_value(env, unsafe { env.value(result) })
+    }
+}
+
+impl<'a> FromNapiValue<'a> for JsFunction<'a> {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        JsFunction::from_value(value)
+    }
+}
+
+impl<'a> IntoNapiValue for JsFunction<'a> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value.raw())
+    }
+}
@@ -0,0 +1,112 @@
+use std::marker::PhantomData;
+
+use napi;
+use napi::{NapiEnv, NapiRef, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue};
+use env::Env;
+use error::Result;
+
+/// A JavaScript value that is only valid within the scope `'a`.
+///
+/// Unlike a raw `NapiValue`, a `JsValue` cannot be stored past the call or
+/// `HandleScope` it was created in. Use a `Reference` for that. Handles
+/// belong to the JavaScript thread, so `JsValue` is neither `Send` nor
+/// `Sync`.
+#[derive(Debug, Clone, Copy)]
+pub struct JsValue<'a> {
+    env: NapiEnv,
+    raw: NapiValue,
+    _scope: PhantomData<(&'a (), *mut ())>,
+}
+
+impl<'a> JsValue<'a> {
+    /// Binds a raw value handle to the scope of `env`.
+    ///
+    /// # Safety
+    ///
+    /// The handle has to be valid for as long as that scope is open.
+    pub unsafe fn from_raw(env: Env<'a>, raw: NapiValue) -> JsValue<'a> {
+        JsValue {
+            env: env.raw(),
+            raw: raw,
+            _scope: PhantomData,
+        }
+    }
+
+    pub fn env(&self) -> Env<'a> {
+        unsafe { Env::from_raw(self.env) }
+    }
+
+    pub fn raw(&self) -> NapiValue {
+        self.raw
+    }
+
+    pub fn type_of(&self) -> Result<NapiValueType> {
+        napi::type_of(self.env, self.raw)
+    }
+
+    /// Compares with `===`.
+    pub fn strict_equals(&self, other: JsValue<'a>) -> Result<bool> {
+        napi::strict_equals(self.env, self.raw, other.raw)
+    }
+
+    /// Checks `value instanceof constructor`, which throws a `TypeError` if
+    /// `constructor` is not callable.
+    pub fn instance_of(&self, constructor: JsValue<'a>) -> Result<bool> {
+        napi::instance_of(self.env, self.raw, constructor.raw)
+    }
+}
+
+impl<'a> IntoNapiValue for JsValue<'a> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.raw)
+    }
+}
+
+impl<'a> FromNapiValue<'a> for JsValue<'a> {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        Ok(value)
+    }
+}
+
+/// A strong, persistent reference to a JavaScript value.
+///
+/// References are not tied to a scope and keep their value alive until they
+/// are dropped. They must only be used and dropped on the JavaScript thread.
+#[derive(Debug)]
+pub struct Reference {
+    env: NapiEnv,
+    raw: NapiRef,
+}
+
+impl Reference {
+    pub fn new(value: JsValue) -> Result<Reference> {
+        let raw = napi::create_reference(value.env, value.raw, 1)?;
+        Ok(Reference {
+            env: value.env,
+            raw: raw,
+        })
+    }
+
+    /// Returns the referenced value as a handle in the scope of `env`.
+    pub fn get<'a>(&self, env: Env<'a>) -> Result<JsValue<'a>> {
+        let raw = napi::get_reference_value(env.raw(), self.raw)?;
+        Ok(unsafe { JsValue::from_raw(env, raw) })
+    }
+
+    pub fn raw(&self) -> NapiRef {
+        self.raw
+    }
+}
+
+impl<'a> FromNapiValue<'a> for Reference {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        Reference::new(value)
+    }
+}
+
+impl Drop for Reference {
+    fn drop(&mut self) {
+        let _ = napi::delete_reference(self.env, self.raw);
+    }
+}
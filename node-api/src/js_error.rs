@@ -0,0 +1,158 @@
+use std::error::Error;
+use std::fmt;
+
+use napi;
+use napi::{NapiEnv, NapiValue};
+use napi_value::IntoNapiValue;
+use env::Env;
+use error::{Result, NapiError, NapiErrorType};
+
+/// The constructor a `JsError` is created with, or a `NapiError` is thrown as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsErrorKind {
+    Error,
+    TypeError,
+    RangeError,
+}
+
+type Property = Box<dyn FnOnce(NapiEnv) -> Result<NapiValue>>;
+
+/// A JavaScript error to be created, with an optional `code`, extra
+/// properties and a `cause`.
+///
+/// ```ignore
+/// JsError::type_error("port must be a number")
+///     .code("ERR_INVALID_ARG_TYPE")
+///     .property("port", port)
+///     .into_napi_error(env)
+/// ```
+///
+/// `JsError::from_error` turns a Rust error and its chain of `source()`
+/// errors into errors linked by their `cause` properties.
+pub struct JsError {
+    kind: JsErrorKind,
+    message: String,
+    code: Option<String>,
+    properties: Vec<(String, Property)>,
+    cause: Option<Box<JsError>>,
+}
+
+impl JsError {
+    /// An `Error` with the given message.
+    pub fn new<S: Into<String>>(message: S) -> JsError {
+        JsError::with_kind(JsErrorKind::Error, message)
+    }
+
+    pub fn type_error<S: Into<String>>(message: S) -> JsError {
+        JsError::with_kind(JsErrorKind::TypeError, message)
+    }
+
+    pub fn range_error<S: Into<String>>(message: S) -> JsError {
+        JsError::with_kind(JsErrorKind::RangeError, message)
+    }
+
+    pub fn with_kind<S: Into<String>>(kind: JsErrorKind, message: S) -> JsError {
+        JsError {
+            kind: kind,
+            message: message.into(),
+            code: None,
+            properties: Vec::new(),
+            cause: None,
+        }
+    }
+
+    /// An `Error` for `error`, with the errors of its `source()` chain as
+    /// nested causes.
+    pub fn from_error<E: Error + ?Sized>(error: &E) -> JsError {
+        let js_error = JsError::new(error.to_string());
+        match error.source() {
+            Some(source) => js_error.cause(JsError::from_error(source)),
+            None => js_error,
+        }
+    }
+
+    pub fn kind(&self) -> JsErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Sets the `code` property, such as `"ERR_INVALID_ARG_TYPE"`.
+    pub fn code<S: Into<String>>(mut self, code: S) -> JsError {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Sets an additional property on the error.
+    pub fn property<S, T>(mut self, name: S, value: T) -> JsError
+        where S: Into<String>,
+              T: IntoNapiValue + 'static
+    {
+        self.properties.push((name.into(), Box::new(move |env| value.into_napi_value(env))));
+        self
+    }
+
+    /// Sets the `cause` property to another error.
+    pub fn cause(mut self, cause: JsError) -> JsError {
+        self.cause = Some(Box::new(cause));
+        self
+    }
+
+    /// Creates the error and wraps it in a `NapiError`, so that returning it
+    /// from a native function throws it. If creating the error fails, that
+    /// failure is returned instead, still to be thrown with this error's kind
+    /// and code.
+    pub fn into_napi_error(self, env: Env) -> NapiError {
+        let kind = self.kind;
+        let code = self.code.clone();
+        let message = self.message.clone();
+        match self.into_napi_value(env.raw()) {
+            Ok(value) => {
+                let mut error = NapiError::new(NapiErrorType::GenericFailure, message);
+                error.kind = kind;
+                error.code = code;
+                error.with_error_object(env.raw(), value)
+            }
+            Err(mut err) => {
+                err.kind = kind;
+                err.code = code;
+                err
+            }
+        }
+    }
+}
+
+impl IntoNapiValue for JsError {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let code = self.code.as_deref();
+        let error = match self.kind {
+            JsErrorKind::Error => napi::create_error(env, code, &self.message)?,
+            JsErrorKind::TypeError => napi::create_type_error(env, code, &self.message)?,
+            JsErrorKind::RangeError => napi::create_range_error(env, code, &self.message)?,
+        };
+        for (name, value) in self.properties {
+            let value = value(env)?;
+            napi::set_named_property(env, error, &name, value)?;
+        }
+        if let Some(cause) = self.cause {
+            let cause = cause.into_napi_value(env)?;
+            napi::set_named_property(env, error, "cause", cause)?;
+        }
+        Ok(error)
+    }
+}
+
+impl fmt::Debug for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let properties: Vec<&str> = self.properties.iter().map(|(name, _)| name.as_str()).collect();
+        f.debug_struct("JsError")
+            .field("kind", &self.kind)
+            .field("message", &self.message)
+            .field("code", &self.code)
+            .field("properties", &properties)
+            .field("cause", &self.cause)
+            .finish()
+    }
+}
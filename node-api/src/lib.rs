@@ -1,39 +1,124 @@
-#![feature(link_args)]
+// Struct fields are initialized as `field: field` throughout the crate.
+#![allow(clippy::redundant_field_names)]
 
 extern crate node_api_sys;
 extern crate futures;
+extern crate node_api_derive;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 
-mod napi;
+pub mod napi;
 mod napi_value;
 mod napi_futures;
+mod env;
+mod handle;
+mod function;
+mod callback;
+mod node_callback;
+mod scope;
+mod bigint;
+mod date;
+mod string;
+mod symbol;
+mod coerce;
+mod class;
+mod js_error;
+mod module;
+#[cfg(feature = "serde")]
+mod napi_serde;
+#[cfg(feature = "serde_json")]
+mod napi_json;
 pub mod error;
+pub mod export;
+pub mod fatal;
 
-pub use napi::*;
-pub use napi_value::{FromNapiValues, IntoNapiValue};
+pub use napi::{NapiEnv, NapiValue, NapiRef, NapiValueType, NapiModule};
+pub use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue, IntoNapiValues};
+pub use env::Env;
+pub use handle::{JsValue, Reference};
+pub use function::JsFunction;
+pub use bigint::{JsBigInt, BigIntOrNumber};
+pub use date::JsDate;
+pub use string::JsStringUtf16;
+pub use symbol::JsSymbol;
+pub use coerce::Coerced;
+pub use class::{JsClass, ClassProperty, Instance};
+pub use js_error::{JsError, JsErrorKind};
+pub use fatal::install_panic_hook;
+pub use module::Exports;
+pub use callback::{JsCallback, PersistentCallback};
+pub use node_callback::{NodeCallback, PersistentNodeCallback};
+pub use scope::{HandleScope, EscapableHandleScope};
+pub use node_api_derive::{napi, IntoNapiValue, FromNapiValue};
+#[cfg(feature = "serde")]
+pub use napi_serde::{Serde, Serializer, Deserializer, SerdeError};
 
+/// Declares the entry point of a native module.
+///
+/// `napi_module!("name")` exports the functions marked with `#[napi]`.
+/// `napi_module!("name", register)` then calls `register(&mut exports)`
+/// with an `Exports` builder to add more exports to. It can return `()` or a
+/// `Result`; see `export::ModuleExports`. Errors it returns and exports that
+/// failed to register are thrown from `require`.
+///
+/// Node.js finds the module through the exported `napi_register_module_v1`
+/// function. For versions of Node.js that predate it,
+/// `napi_module!(legacy "name", register)` registers the module with
+/// `napi_module_register` from a static constructor instead.
+///
+/// On macOS, the library has to be linked with
+/// `-undefined dynamic_lookup`, for example with
+/// `cargo:rustc-cdylib-link-arg=-Wl,-undefined,dynamic_lookup` in a build
+/// script, because the N-API symbols are provided by the `node` executable.
 #[macro_export]
 macro_rules! napi_module {
-    ($module:expr, $register_func:ident) => {
-const NAPI_MODULE_VERSION: std::os::raw::c_int = 1;
-
-#[cfg_attr(target_os = "macos", link_args = "-Wl,-undefined,dynamic_lookup")]
-extern "C" {}
-
-#[cfg_attr(target_os = "linux", link_section = ".ctors")]
-#[cfg_attr(target_os = "macos", link_section = "__DATA,__mod_init_func")]
-#[cfg_attr(target_os = "windows", link_section = ".CRT$XCU")]
-pub static REGISTER_FOO: extern "C" fn() = {
-    extern "C" fn __load_napi_module() {
-        node_api::module_register(node_api::NapiModule {
-                            version: NAPI_MODULE_VERSION,
-                            flags: 0,
-                            filename: $module.to_string(),
-                            register_func: Some($register_func),
-                            modname: $module.to_string(),
-                        })
-                .expect("error registering module");
-    }
-    __load_napi_module
-};
-}}
+    (legacy $module:expr) => {
+        napi_module!(legacy $module, |_| ());
+    };
+    (legacy $module:expr, $register_func:expr) => {
+        #[doc(hidden)]
+        #[used]
+        #[cfg_attr(target_os = "linux", link_section = ".init_array")]
+        #[cfg_attr(target_os = "macos", link_section = "__DATA,__mod_init_func")]
+        #[cfg_attr(target_os = "windows", link_section = ".CRT$XCU")]
+        static __NAPI_LOAD_MODULE: extern "C" fn() = {
+            unsafe extern "C" fn __napi_register(env: $crate::NapiEnv,
+                                                 exports: $crate::NapiValue)
+                                                 -> $crate::NapiValue {
+                $crate::export::register_module(env, exports, $register_func)
+            }
 
+            extern "C" fn __load_napi_module() {
+                let module = $crate::NapiModule {
+                    version: 1,
+                    flags: 0,
+                    filename: $module.to_string(),
+                    register_func: Some(__napi_register),
+                    modname: $module.to_string(),
+                };
+                if let Err(err) = $crate::napi::module_register(module) {
+                    let location = $module.to_string();
+                    $crate::fatal::fatal_error(Some(&location), &format!("cannot register module: {}", err));
+                }
+            }
+            __load_napi_module
+        };
+    };
+    ($module:expr) => {
+        napi_module!($module, |_| ());
+    };
+    ($module:expr, $register_func:expr) => {
+        #[doc(hidden)]
+        #[no_mangle]
+        pub unsafe extern "C" fn napi_register_module_v1(env: $crate::NapiEnv,
+                                                         exports: $crate::NapiValue)
+                                                         -> $crate::NapiValue {
+            $crate::export::register_module(env, exports, $register_func)
+        }
+    };
+}
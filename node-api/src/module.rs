@@ -0,0 +1,155 @@
+use napi;
+use napi::NapiValue;
+use napi_value::{FromNapiValues, IntoNapiValue};
+use class;
+use class::JsClass;
+use handle::JsValue;
+use env::Env;
+use export;
+use error::{Result, NapiError, NapiErrorType};
+
+/// The exports of a module, passed to the register function of
+/// `napi_module!`.
+///
+/// ```ignore
+/// fn register(exports: &mut Exports) {
+///     exports.function("add", add)
+///         .class::<Counter>()
+///         .value("version", "1.0.0")
+///         .submodule("math", |math| {
+///             math.value("PI", ::std::f64::consts::PI);
+///         });
+/// }
+/// ```
+///
+/// Failing exports do not stop the others from being registered. The errors
+/// are collected and thrown from `require` as a single error naming every
+/// export that failed.
+#[derive(Debug)]
+pub struct Exports<'a> {
+    env: Env<'a>,
+    object: JsValue<'a>,
+    prefix: String,
+    errors: Vec<(String, NapiError)>,
+}
+
+impl<'a> Exports<'a> {
+    /// Adds exports to `object`.
+    pub fn new(env: Env<'a>, object: JsValue<'a>) -> Exports<'a> {
+        Exports {
+            env: env,
+            object: object,
+            prefix: String::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn env(&self) -> Env<'a> {
+        self.env
+    }
+
+    /// The object the exports are added to.
+    pub fn object(&self) -> JsValue<'a> {
+        self.object
+    }
+
+    /// Exports `value` in place of the exports object, like assigning to
+    /// `module.exports`. Later exports are added to `value`.
+    pub fn replace(&mut self, value: JsValue<'a>) -> &mut Exports<'a> {
+        self.object = value;
+        self
+    }
+
+    /// Exports a native function.
+    pub fn function<F, T, R>(&mut self, name: &str, f: F) -> &mut Exports<'a>
+        where F: for<'s> Fn(Env<'s>, JsValue<'s>, T) -> R + 'static,
+              T: for<'s> FromNapiValues<'s>,
+              R: IntoNapiValue
+    {
+        let function = napi::create_function(self.env.raw(), name, f);
+        self.set(name, function)
+    }
+
+    /// Exports a native function that keeps state between calls, such as a
+    /// cache or a counter.
+    pub fn function_mut<F, T, R>(&mut self, name: &str, f: F) -> &mut Exports<'a>
+        where F: for<'s> FnMut(Env<'s>, JsValue<'s>, T) -> R + 'static,
+              T: for<'s> FromNapiValues<'s>,
+              R: IntoNapiValue
+    {
+        let function = napi::create_function_mut(self.env.raw(), name, f);
+        self.set(name, function)
+    }
+
+    /// Defines the class `T` and exports its constructor under `T::NAME`.
+    pub fn class<T: JsClass>(&mut self) -> &mut Exports<'a> {
+        let constructor = class::define::<T>(self.env).map(|constructor| constructor.raw());
+        self.set(T::NAME, constructor)
+    }
+
+    /// Exports a value, such as a constant.
+    pub fn value<T: IntoNapiValue>(&mut self, name: &str, value: T) -> &mut Exports<'a> {
+        let value = value.into_napi_value(self.env.raw());
+        self.set(name, value)
+    }
+
+    /// Exports an object whose properties are added by `f`.
+    pub fn submodule<F>(&mut self, name: &str, f: F) -> &mut Exports<'a>
+        where F: FnOnce(&mut Exports<'a>)
+    {
+        let object = match self.env.create_object() {
+            Ok(object) => object,
+            Err(err) => return self.fail(name, err),
+        };
+        let mut submodule = Exports {
+            env: self.env,
+            object: object,
+            prefix: format!("{}{}.", self.prefix, name),
+            errors: Vec::new(),
+        };
+        f(&mut submodule);
+        self.errors.append(&mut submodule.errors);
+        self.set(name, Ok(submodule.object.raw()))
+    }
+
+    /// Exports the functions marked with `#[napi]`. `napi_module!` does this
+    /// before calling the register function.
+    #[doc(hidden)]
+    pub fn registered(&mut self) -> &mut Exports<'a> {
+        for (name, create) in export::registered() {
+            let value = create(self.env.raw());
+            self.set(name, value);
+        }
+        self
+    }
+
+    /// Returns an error describing every export that failed, if any.
+    pub fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        let failures: Vec<String> = self.errors
+            .iter()
+            .map(|(name, err)| format!("`{}`: {}", name, err.error_message))
+            .collect();
+        Err(NapiError::new(NapiErrorType::GenericFailure,
+                           format!("cannot register {} export{}: {}",
+                                   failures.len(),
+                                   if failures.len() == 1 { "" } else { "s" },
+                                   failures.join("; "))))
+    }
+
+    fn set(&mut self, name: &str, value: Result<NapiValue>) -> &mut Exports<'a> {
+        let env = self.env.raw();
+        let object = self.object.raw();
+        match value.and_then(|value| napi::set_named_property(env, object, name, value)) {
+            Ok(()) => self,
+            Err(err) => self.fail(name, err),
+        }
+    }
+
+    fn fail(&mut self, name: &str, err: NapiError) -> &mut Exports<'a> {
+        self.errors.push((format!("{}{}", self.prefix, name), err));
+        self
+    }
+}
@@ -1,14 +1,25 @@
+// The functions in this module wrap N-API calls one to one. Like values,
+// references, scopes and async work handles are passed on unchecked.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::cell::RefCell;
 use std::ffi::CString;
-use std::{ptr,mem,f64,usize,os};
+use std::{ptr,f64,os,process};
 use std::result::Result as StdResult;
+use std::panic::Location;
 use node_api_sys::*;
 
 use napi_value::{FromNapiValues, IntoNapiValue};
+use env::Env;
+use handle::JsValue;
 use error::*;
+use fatal;
 
 pub type NapiEnv = napi_env;
 pub type NapiRef = napi_ref;
 pub type NapiValue = napi_value;
+pub type NapiHandleScope = napi_handle_scope;
+pub type NapiEscapableHandleScope = napi_escapable_handle_scope;
 
 #[derive(Debug, Clone)]
 pub struct NapiModule {
@@ -31,6 +42,7 @@ pub enum NapiValueType {
     Object,
     Function,
     External,
+    BigInt,
 }
 
 impl From<napi_valuetype> for NapiValueType {
@@ -45,117 +57,211 @@ impl From<napi_valuetype> for NapiValueType {
             napi_valuetype::napi_object => NapiValueType::Object,
             napi_valuetype::napi_function => NapiValueType::Function,
             napi_valuetype::napi_external => NapiValueType::External,
+            napi_valuetype::napi_bigint => NapiValueType::BigInt,
         }
     }
 }
 
-pub fn napi_either<T>(env: NapiEnv, status: napi_status, val: T) -> Result<T> {
-    match status {
-        napi_status::napi_ok => Ok(val),
-        _err => Err(get_last_napi_error(env).expect("error fetching last napi error")),
-    }
+/// Turns the status of the N-API function `operation` into a `Result`. Errors
+/// record the operation and the location of the caller.
+#[track_caller]
+pub fn napi_either<T>(env: NapiEnv, operation: &'static str, status: napi_status, val: T) -> Result<T> {
+    let mut err = match status {
+        napi_status::napi_ok => return Ok(val),
+        napi_status::napi_pending_exception => {
+            NapiError::new(NapiErrorType::PendingException, "a JavaScript exception is pending")
+        }
+        _ => {
+            // the error info has to be read before making any other call
+            get_last_napi_error(env, status)
+        }
+    };
+    err.operation = Some(operation);
+    err.location = Some(Location::caller());
+    Err(catch_exception(env, err))
 }
 
-fn get_last_error_info(env: napi_env) -> StdResult<napi_extended_error_info, napi_status> {
+/// Clears a pending exception and attaches it to `err`, so that JavaScript
+/// can continue to run and the exception can be inspected or rethrown.
+fn catch_exception(env: NapiEnv, mut err: NapiError) -> NapiError {
+    let mut pending = false;
+    let mut value: NapiValue = 0;
     unsafe {
-        let mut info: *const napi_extended_error_info =
-            Box::into_raw(Box::new(mem::uninitialized()));
-        let status = napi_get_last_error_info(env, &mut info);
-        match status {
-            napi_status::napi_ok => Ok(*info),
-            _ => Err(status),
+        if napi_is_exception_pending(env, &mut pending) != napi_status::napi_ok || !pending ||
+           napi_get_and_clear_last_exception(env, &mut value) != napi_status::napi_ok {
+            return err;
         }
     }
+    let exception = read_exception(env, value);
+    if let Some(ref message) = exception.message {
+        err.error_message = message.clone();
+    }
+    err.exception = Some(Box::new(exception));
+    err
 }
 
-fn get_last_napi_error(env: NapiEnv) -> StdResult<NapiError, NapiErrorType> {
-    get_last_error_info(env)
-        .map(|res| NapiError::from(res))
-        .map_err(|err| NapiErrorType::from(err))
+fn read_exception(env: NapiEnv, value: NapiValue) -> JsException {
+    match type_of(env, value) {
+        Ok(NapiValueType::Object) | Ok(NapiValueType::Function) => {
+            JsException::new(env,
+                             value,
+                             read_string_property(env, value, "name"),
+                             read_string_property(env, value, "message"),
+                             read_string_property(env, value, "stack"))
+        }
+        Ok(NapiValueType::Symbol) | Err(_) => JsException::new(env, value, None, None, None),
+        Ok(_) => {
+            // converting other primitives to strings cannot throw
+            let message = coerce_to_string(env, value).and_then(|string| get_value_string_utf8(env, string));
+            JsException::new(env, value, None, message.ok(), None)
+        }
+    }
 }
 
+/// Reads a string property of a thrown value. Getters may throw themselves,
+/// so this goes around `napi_either` to not catch exceptions recursively.
+fn read_string_property(env: NapiEnv, object: NapiValue, name: &str) -> Option<String> {
+    let name = CString::new(name).ok()?;
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_named_property(env, object, name.as_ptr(), &mut result) };
+    if status != napi_status::napi_ok {
+        let mut exception: NapiValue = 0;
+        unsafe { napi_get_and_clear_last_exception(env, &mut exception) };
+        return None;
+    }
+    match type_of(env, result) {
+        Ok(NapiValueType::String) => get_value_string_utf8(env, result).ok(),
+        _ => None,
+    }
+}
+
+/// Reads the error info of the last failed N-API call. Falls back to an error
+/// made from `status` if the info cannot be read.
+fn get_last_napi_error(env: NapiEnv, status: napi_status) -> NapiError {
+    let mut info: *const napi_extended_error_info = ptr::null();
+    let info_status = unsafe { napi_get_last_error_info(env, &mut info) };
+    if info_status == napi_status::napi_ok && !info.is_null() {
+        // the info is owned by the environment and overwritten by the next
+        // call, so it is copied out right away
+        let mut err = NapiError::from(unsafe { *info });
+        err.error_code = NapiErrorType::from(status);
+        err
+    } else {
+        NapiError::new(NapiErrorType::from(status),
+                       format!("N-API call failed with status {}", status.0))
+    }
+}
+
+/// Reports an unrecoverable error to Node.js, which prints `location` and
+/// `message` in its fatal error report and aborts the process.
+pub fn fatal_error(location: Option<&str>, message: &str) -> ! {
+    let (location, location_len) = match location {
+        Some(location) => (location.as_ptr() as *const os::raw::c_char, location.len()),
+        None => (ptr::null(), 0),
+    };
+    unsafe {
+        napi_fatal_error(location,
+                         location_len,
+                         message.as_ptr() as *const os::raw::c_char,
+                         message.len());
+    }
+    process::abort()
+}
+
+/// Registers a module through the legacy `napi_module_register`. Node.js
+/// keeps pointers to the module and its names, so they are leaked.
 pub fn module_register(mod_: NapiModule) -> StdResult<(), NapiError> {
-    let module = &mut napi_module {
-                          nm_version: mod_.version,
-                          nm_flags: mod_.flags,
-                          nm_filename: CString::new(mod_.filename)?.as_ptr(),
-                          nm_register_func: mod_.register_func,
-                          nm_modname: try!(CString::new(mod_.modname)).as_ptr(),
-                          nm_priv: ptr::null_mut(),
-                          reserved: [ptr::null_mut(),
-                                     ptr::null_mut(),
-                                     ptr::null_mut(),
-                                     ptr::null_mut()],
-                      };
+    let filename = CString::new(mod_.filename)?;
+    let modname = CString::new(mod_.modname)?;
+    let module = Box::new(napi_module {
+                              nm_version: mod_.version,
+                              nm_flags: mod_.flags,
+                              nm_filename: filename.into_raw(),
+                              nm_register_func: mod_.register_func,
+                              nm_modname: modname.into_raw(),
+                              nm_priv: ptr::null_mut(),
+                              reserved: [ptr::null_mut(),
+                                         ptr::null_mut(),
+                                         ptr::null_mut(),
+                                         ptr::null_mut()],
+                          });
     unsafe {
-        napi_module_register(module);
+        napi_module_register(Box::leak(module));
     }
     Ok(())
 }
 
+#[track_caller]
 pub fn get_undefined(env: NapiEnv) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_get_undefined(env, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_get_undefined", status, napi_val)
     }
 }
 
+#[track_caller]
 pub fn get_null(env: NapiEnv) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_get_null(env, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_get_null", status, napi_val)
     }
 }
 
+#[track_caller]
 pub fn get_global(env: NapiEnv) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_get_global(env, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_get_global", status, napi_val)
     }
 }
 
+#[track_caller]
 pub fn get_boolean(env: NapiEnv, value: bool) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_get_boolean(env, value, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_get_boolean", status, napi_val)
     }
 }
 
+#[track_caller]
 pub fn create_object(env: NapiEnv) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_create_object(env, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_create_object", status, napi_val)
     }
 }
 
+#[track_caller]
 pub fn create_array(env: NapiEnv) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_create_array(env, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_create_array", status, napi_val)
     }
 }
 
+#[track_caller]
 pub fn array_with_length(env: NapiEnv, size: usize) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = napi_create_array_with_length(env, size, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, "napi_create_array_with_length", status, napi_val)
     }
 }
 
 macro_rules! create_number {
     ($name:ident, $sys_name:ident, $value_type:ty) => {
+#[track_caller]
 pub fn $name(env: NapiEnv, value: $value_type) -> Result<NapiValue> {
     unsafe {
-        let mut napi_val: NapiValue = mem::uninitialized();
+        let mut napi_val: NapiValue = 0;
         let status = $sys_name(env, value, &mut napi_val);
-        napi_either(env, status, napi_val)
+        napi_either(env, stringify!($sys_name), status, napi_val)
     }
 }
     };
@@ -167,119 +273,360 @@ create_number!(create_u32,    napi_create_uint32, u32);
 create_number!(create_i64,    napi_create_int64,  i64);
 
 
+#[track_caller]
 pub fn create_string_utf8<T>(env: NapiEnv, val: T) -> Result<NapiValue>
     where T: AsRef<str>
 {
     let mut napi_val: NapiValue = 0;
-    let converted_value = CString::new(val.as_ref())?;
+    let val = val.as_ref();
+    // pass the length explicitly, so that strings may contain NUL characters
     let status = unsafe {
-        napi_create_string_utf8(env,
-                                converted_value.as_ptr(),
-                                usize::MAX, // indicates 0-terminated string
-                                &mut napi_val)
+        napi_create_string_utf8(env, val.as_ptr() as *const os::raw::c_char, val.len(), &mut napi_val)
     };
-    napi_either(env, status, napi_val)
+    napi_either(env, "napi_create_string_utf8", status, napi_val)
+}
+
+/// Creates a string from Latin-1 bytes, each of which is one character.
+#[track_caller]
+pub fn create_string_latin1(env: NapiEnv, val: &[u8]) -> Result<NapiValue> {
+    let mut napi_val: NapiValue = 0;
+    let status = unsafe {
+        napi_create_string_latin1(env, val.as_ptr() as *const os::raw::c_char, val.len(), &mut napi_val)
+    };
+    napi_either(env, "napi_create_string_latin1", status, napi_val)
+}
+
+/// Creates a string from UTF-16 code units, which may include unpaired
+/// surrogates.
+#[track_caller]
+pub fn create_string_utf16(env: NapiEnv, val: &[u16]) -> Result<NapiValue> {
+    let mut napi_val: NapiValue = 0;
+    let status = unsafe { napi_create_string_utf16(env, val.as_ptr(), val.len(), &mut napi_val) };
+    napi_either(env, "napi_create_string_utf16", status, napi_val)
 }
 
 //     pub fn napi_create_symbol(env: napi_env, description: napi_value,
 //                               result: *mut napi_value) -> napi_status;
 
+#[track_caller]
+pub fn create_symbol(env: NapiEnv, description: Option<&str>) -> Result<NapiValue> {
+    let description = match description {
+        Some(description) => create_string_utf8(env, description)?,
+        None => 0,
+    };
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_symbol(env, description, &mut result) };
+    napi_either(env, "napi_create_symbol", status, result)
+}
+
+/// Looks up a well-known symbol such as `iterator` on the global `Symbol`.
+#[track_caller]
+pub fn get_well_known_symbol(env: NapiEnv, name: &str) -> Result<NapiValue> {
+    let symbol = get_named_property(env, get_global(env)?, "Symbol")?;
+    let result = get_named_property(env, symbol, name)?;
+    if type_of(env, result)? != NapiValueType::Symbol {
+        return Err(NapiError::new(NapiErrorType::InvalidArg,
+                                  format!("`Symbol.{}` is not a well-known symbol", name)));
+    }
+    Ok(result)
+}
+
+/// Creates a function that calls `f` with the environment, `this` and the
+/// arguments of each call, converted to `T`. The function object owns `f`,
+/// which is dropped when the function is garbage collected.
+///
+/// `f` is called with handles in the scope of the call, which cannot be kept
+/// past it, so `T` and `R` have to own their data. Functions taking or
+/// returning `JsValue`s and other handles use `create_function_with_args` or
+/// `#[napi]`.
+///
+/// ```compile_fail
+/// # extern crate node_api;
+/// # use std::cell::RefCell;
+/// # use node_api::{napi, JsValue, NapiEnv};
+/// thread_local!(static KEPT: RefCell<Option<JsValue<'static>>> = RefCell::new(None));
+///
+/// fn keeps_this(env: NapiEnv) {
+///     napi::create_function(env, "keepsThis", |_, this, ()| {
+///         KEPT.with(|kept| *kept.borrow_mut() = Some(this));
+///     }).unwrap();
+/// }
+/// # fn main() {}
+/// ```
+#[track_caller]
 pub fn create_function<F, T, R>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
-    where F: Fn(NapiEnv, NapiValue, T) -> R,
-          T: FromNapiValues,
+    where F: for<'a> Fn(Env<'a>, JsValue<'a>, T) -> R + 'static,
+          T: for<'a> FromNapiValues<'a>,
           R: IntoNapiValue
 {
-    unsafe extern "C" fn wrapper<F, T, R>(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue
-        where F: Fn(NapiEnv, NapiValue, T) -> R,
-              T: FromNapiValues,
-              R: IntoNapiValue
-    {
-        let mut argc: usize = 16;
-        let mut argv: [NapiValue; 16] = mem::uninitialized();
-        let mut user_data = ptr::null_mut();
-        let mut this: NapiValue = 0;
-        let status = napi_get_cb_info(env,
-                                      cbinfo,
-                                      &mut argc,
-                                      argv.as_mut_ptr(),
-                                      &mut this,
-                                      &mut user_data);
-        assert!(status == napi_status::napi_ok);
-        assert!(user_data != ptr::null_mut());
-
-        let args =
-            T::from_napi_values(env, this, &argv[0..argc]).expect("cannot convert arguments");
-
-        let callback: Box<Option<F>> = Box::from_raw(user_data as *mut Option<F>);
-
-        let return_value = callback.expect("no callback found")(env, this, args);
-        return_value
-            .into_napi_value(env)
-            .unwrap_or(get_undefined(env).unwrap())
-    }
+    create_function_with_args(env, utf8name, move |env, this, args| {
+        let args = T::from_napi_values(env, this, args)?;
+        env.to_js(f(env, this, args))
+    })
+}
 
-    let boxed_f = Box::new(Some(f));
-    let user_data = Box::into_raw(boxed_f) as *mut os::raw::c_void;
+/// Like `create_function`, but passes the arguments of each call as handles
+/// and returns a handle created in the call.
+#[track_caller]
+pub fn create_function_with_args<F>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
+    where F: for<'a> Fn(Env<'a>, JsValue<'a>, &[JsValue<'a>]) -> Result<JsValue<'a>> + 'static
+{
+    let user_data = Box::into_raw(Box::new(f)) as *mut os::raw::c_void;
     let mut napi_val: NapiValue = 0;
-    let name = CString::new(utf8name)?;
     let status = unsafe {
         napi_create_function(env,
-                             name.into_raw(),
+                             utf8name.as_ptr() as *const os::raw::c_char,
                              utf8name.len(),
-                             Some(wrapper::<F, T, R>),
+                             Some(call_closure::<F>),
                              user_data,
                              &mut napi_val)
     };
-    napi_either(env, status, napi_val)
+    let status = own_closure::<F>(env, status, napi_val, user_data);
+    napi_either(env, "napi_create_function", status, napi_val)
 }
 
+/// Hands the boxed closure `user_data` over to `function`, which drops it
+/// when it is garbage collected, or drops it right away if creating the
+/// function failed.
+fn own_closure<F>(env: NapiEnv,
+                  status: napi_status,
+                  function: NapiValue,
+                  user_data: *mut os::raw::c_void) -> napi_status {
+    let status = if status == napi_status::napi_ok {
+        unsafe {
+            napi_add_finalizer(env,
+                               function,
+                               user_data,
+                               Some(finalize_box::<F>),
+                               ptr::null_mut(),
+                               ptr::null_mut())
+        }
+    } else {
+        status
+    };
+    if status != napi_status::napi_ok {
+        drop(unsafe { Box::from_raw(user_data as *mut F) });
+    }
+    status
+}
 
-//     pub fn napi_create_error(env: napi_env, msg: napi_value,
-//                              result: *mut napi_value) -> napi_status;
+/// Calls the closure passed as the data of a function, with the handles of
+/// the call.
+unsafe extern "C" fn call_closure<F>(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue
+    where F: for<'a> Fn(Env<'a>, JsValue<'a>, &[JsValue<'a>]) -> Result<JsValue<'a>> + 'static
+{
+    // ask for the number of arguments first, then read all of them
+    let mut argc: usize = 0;
+    let mut user_data = ptr::null_mut();
+    let mut this: NapiValue = 0;
+    let mut status = napi_get_cb_info(env,
+                                      cbinfo,
+                                      &mut argc,
+                                      ptr::null_mut(),
+                                      &mut this,
+                                      &mut user_data);
+    let mut argv: Vec<NapiValue> = vec![0; argc];
+    if status == napi_status::napi_ok && argc > 0 {
+        status = napi_get_cb_info(env,
+                                  cbinfo,
+                                  &mut argc,
+                                  argv.as_mut_ptr(),
+                                  ptr::null_mut(),
+                                  ptr::null_mut());
+    }
+    if status != napi_status::napi_ok || user_data.is_null() {
+        fatal_error(Some("napi_get_cb_info"), "cannot read the callback info of a native function");
+    }
 
+    // the closure stays owned by the function object
+    let callback = &*(user_data as *const F);
+    let result = fatal::catch_panic(|| {
+        Env::scoped(env, |env| {
+            let this = env.value(this);
+            let args: Vec<JsValue> = argv.iter().map(|&arg| env.value(arg)).collect();
+            callback(env, this, &args).map(|result| result.raw())
+        })
+    });
+    match result.unwrap_or_else(|panic| Err(panic.into_error())) {
+        Ok(value) => value,
+        Err(err) => {
+            let message = err.error_message.clone();
+            match err.into_napi_value(env) {
+                Ok(exception) => {
+                    let _ = throw(env, exception);
+                }
+                // at least let JavaScript know that the call failed
+                Err(_) => {
+                    let _ = throw_error(env, &message);
+                }
+            }
+            // N-API turns a null result into `undefined`
+            0
+        }
+    }
+}
 
-//     pub fn napi_create_type_error(env: napi_env, msg: napi_value,
-//                                   result: *mut napi_value) -> napi_status;
+/// Creates a function that calls `f`, which may keep state between calls.
+/// Calling the function again while `f` is running, for example from a
+/// callback it calls, throws an error instead of calling `f`.
+#[track_caller]
+pub fn create_function_mut<F, T, R>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
+    where F: for<'a> FnMut(Env<'a>, JsValue<'a>, T) -> R + 'static,
+          T: for<'a> FromNapiValues<'a>,
+          R: IntoNapiValue
+{
+    let f = RefCell::new(f);
+    create_function(env, utf8name, move |env, this, args: T| match f.try_borrow_mut() {
+        Ok(mut f) => Ok(f(env, this, args)),
+        Err(_) => {
+            Err(NapiError::new(NapiErrorType::GenericFailure,
+                               "a native function cannot be called while it is running"))
+        }
+    })
+}
 
 
-//     pub fn napi_create_range_error(env: napi_env, msg: napi_value,
-//                                    result: *mut napi_value) -> napi_status;
+//     pub fn napi_create_error(env: napi_env, code: napi_value, msg: napi_value,
+//                              result: *mut napi_value) -> napi_status;
+//     pub fn napi_create_type_error(env: napi_env, code: napi_value,
+//                                   msg: napi_value, result: *mut napi_value)
+//      -> napi_status;
+//     pub fn napi_create_range_error(env: napi_env, code: napi_value,
+//                                    msg: napi_value, result: *mut napi_value)
+//      -> napi_status;
 
+macro_rules! create_error {
+    ($name:ident, $sys_name:ident) => {
+/// Creates an error with the given message. If `code` is given, it is set as
+/// the `code` property, like the `ERR_*` codes of Node.js errors.
+#[track_caller]
+pub fn $name(env: NapiEnv, code: Option<&str>, msg: &str) -> Result<NapiValue> {
+    let code = match code {
+        Some(code) => create_string_utf8(env, code)?,
+        None => 0,
+    };
+    let message = create_string_utf8(env, msg)?;
+    let mut result: NapiValue = 0;
+    let status = unsafe { $sys_name(env, code, message, &mut result) };
+    napi_either(env, stringify!($sys_name), status, result)
+}
+    };
+}
 
+create_error!(create_error,       napi_create_error);
+create_error!(create_type_error,  napi_create_type_error);
+create_error!(create_range_error, napi_create_range_error);
+
+
+#[track_caller]
 pub fn type_of(env: NapiEnv, napi_value: NapiValue) -> Result<NapiValueType> {
     let mut napi_value_type = napi_valuetype::napi_undefined;
     let status = unsafe { napi_typeof(env, napi_value, &mut napi_value_type) };
-    napi_either(env, status, NapiValueType::from(napi_value_type))
+    napi_either(env, "napi_typeof", status, NapiValueType::from(napi_value_type))
 }
 
+#[track_caller]
 pub fn get_value_double(env: NapiEnv, value: NapiValue) -> Result<f64> {
     let mut result: f64 = f64::NAN;
     let status = unsafe { napi_get_value_double(env, value, &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_get_value_double", status, result)
 }
 
 //     pub fn napi_get_value_int32(env: napi_env, value: napi_value,
 //                                 result: *mut i32) -> napi_status;
+#[track_caller]
+pub fn get_value_int32(env: NapiEnv, value: NapiValue) -> Result<i32> {
+    let mut result: i32 = 0;
+    let status = unsafe { napi_get_value_int32(env, value, &mut result) };
+    napi_either(env, "napi_get_value_int32", status, result)
+}
 
 
+#[track_caller]
 pub fn get_value_uint32(env: NapiEnv, value: NapiValue) -> Result<u32> {
     let mut result: u32 = 0;
     let status = unsafe { napi_get_value_uint32(env, value, &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_get_value_uint32", status, result)
 }
 
+#[track_caller]
 pub fn get_value_int64(env: NapiEnv, value: NapiValue) -> Result<i64> {
     let mut result: i64 = 0;
     let status = unsafe { napi_get_value_int64(env, value, &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_get_value_int64", status, result)
 }
 
+#[track_caller]
+pub fn create_bigint_i64(env: NapiEnv, value: i64) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_bigint_int64(env, value, &mut result) };
+    napi_either(env, "napi_create_bigint_int64", status, result)
+}
 
+#[track_caller]
+pub fn create_bigint_u64(env: NapiEnv, value: u64) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_bigint_uint64(env, value, &mut result) };
+    napi_either(env, "napi_create_bigint_uint64", status, result)
+}
 
+/// Creates a BigInt from its sign and its magnitude as 64-bit words, least
+/// significant first.
+#[track_caller]
+pub fn create_bigint_words(env: NapiEnv, negative: bool, words: &[u64]) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe {
+        napi_create_bigint_words(env, negative as os::raw::c_int, words.len(), words.as_ptr(), &mut result)
+    };
+    napi_either(env, "napi_create_bigint_words", status, result)
+}
+
+/// Returns the value of a BigInt truncated to an `i64`, and whether it fit
+/// without loss.
+#[track_caller]
+pub fn get_value_bigint_i64(env: NapiEnv, value: NapiValue) -> Result<(i64, bool)> {
+    let mut result: i64 = 0;
+    let mut lossless = false;
+    let status = unsafe { napi_get_value_bigint_int64(env, value, &mut result, &mut lossless) };
+    napi_either(env, "napi_get_value_bigint_int64", status, (result, lossless))
+}
+
+/// Returns the value of a BigInt truncated to a `u64`, and whether it fit
+/// without loss.
+#[track_caller]
+pub fn get_value_bigint_u64(env: NapiEnv, value: NapiValue) -> Result<(u64, bool)> {
+    let mut result: u64 = 0;
+    let mut lossless = false;
+    let status = unsafe { napi_get_value_bigint_uint64(env, value, &mut result, &mut lossless) };
+    napi_either(env, "napi_get_value_bigint_uint64", status, (result, lossless))
+}
+
+/// Returns the sign of a BigInt (`true` if negative) and its magnitude as
+/// 64-bit words, least significant first.
+#[track_caller]
+pub fn get_value_bigint_words(env: NapiEnv, value: NapiValue) -> Result<(bool, Vec<u64>)> {
+    let mut word_count: usize = 0;
+    let status = unsafe {
+        napi_get_value_bigint_words(env, value, ptr::null_mut(), &mut word_count, ptr::null_mut())
+    };
+    napi_either(env, "napi_get_value_bigint_words", status, ())?;
+
+    let mut sign_bit: os::raw::c_int = 0;
+    let mut words = vec![0; word_count];
+    let status = unsafe {
+        napi_get_value_bigint_words(env, value, &mut sign_bit, &mut word_count, words.as_mut_ptr())
+    };
+    words.truncate(word_count);
+    napi_either(env, "napi_get_value_bigint_words", status, (sign_bit != 0, words))
+}
+
+
+
+#[track_caller]
 pub fn get_value_bool(env: NapiEnv, value: NapiValue) -> Result<bool> {
     let mut result = false;
     let status = unsafe { napi_get_value_bool(env, value, &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_get_value_bool", status, result)
 }
 
 //     pub fn napi_get_value_string_latin1(env: napi_env, value: napi_value,
@@ -287,39 +634,21 @@ pub fn get_value_bool(env: NapiEnv, value: NapiValue) -> Result<bool> {
 //                                         bufsize: usize, result: *mut usize)
 //      -> napi_status;
 
+/// Returns the string as Latin-1 bytes. Characters beyond U+00FF do not fit
+/// and are truncated to their lowest byte.
+#[track_caller]
+pub fn get_value_string_latin1(env: NapiEnv, value: NapiValue) -> Result<Vec<u8>> {
+    get_value_string(env, "napi_get_value_string_latin1", |buf: *mut u8, bufsize, result| unsafe {
+        napi_get_value_string_latin1(env, value, buf as *mut os::raw::c_char, bufsize, result)
+    })
+}
 
+#[track_caller]
 pub fn get_value_string_utf8(env: NapiEnv, value: NapiValue) -> Result<String> {
-    let mut size: usize = 0;
-    // obtain string length in bytes to determine buffer size
-    let size_status =
-        unsafe { napi_get_value_string_utf8(env, value, ptr::null_mut(), 0, &mut size) };
-    napi_either(env, size_status, size)?;
-    let mut buffer: Vec<u8> = Vec::with_capacity(size + 1);
-    let mut written: usize = 0;
-    let status = unsafe {
-        napi_get_value_string_utf8(env,
-                                   value,
-                                   buffer.as_mut_ptr() as *mut i8,
-                                   size + 1,
-                                   &mut written)
-    };
-    match written == size {
-        true => {
-            napi_either(env, status, unsafe {
-                buffer.set_len(size);
-                String::from_utf8(buffer)?
-            })
-        }
-        false => {
-            Err(NapiError {
-                    error_message: format!("buffer size mismatch, expected {}, got {}",
-                                           size,
-                                           written),
-                    engine_error_code: 0,
-                    error_code: NapiErrorType::GenericFailure,
-                })
-        }
-    }
+    let buffer = get_value_string(env, "napi_get_value_string_utf8", |buf: *mut u8, bufsize, result| unsafe {
+        napi_get_value_string_utf8(env, value, buf as *mut os::raw::c_char, bufsize, result)
+    })?;
+    Ok(String::from_utf8(buffer)?)
 }
 
 
@@ -328,49 +657,122 @@ pub fn get_value_string_utf8(env: NapiEnv, value: NapiValue) -> Result<String> {
 //                                        buf: *mut char16_t, bufsize: usize,
 //                                        result: *mut usize) -> napi_status;
 
+/// Returns the string as UTF-16 code units, keeping unpaired surrogates.
+#[track_caller]
+pub fn get_value_string_utf16(env: NapiEnv, value: NapiValue) -> Result<Vec<u16>> {
+    get_value_string(env, "napi_get_value_string_utf16", |buf: *mut u16, bufsize, result| unsafe {
+        napi_get_value_string_utf16(env, value, buf, bufsize, result)
+    })
+}
 
-//     pub fn napi_coerce_to_bool(env: napi_env, value: napi_value,
-//                                result: *mut napi_value) -> napi_status;
+/// Reads a string with one of the `napi_get_value_string_*` functions, which
+/// take a buffer with room for a terminating NUL and report the length
+/// without it.
+#[track_caller]
+fn get_value_string<C, F>(env: NapiEnv, operation: &'static str, get: F) -> Result<Vec<C>>
+    where C: Copy + Default,
+          F: Fn(*mut C, usize, *mut usize) -> napi_status
+{
+    let mut size: usize = 0;
+    napi_either(env, operation, get(ptr::null_mut(), 0, &mut size), ())?;
+    let mut buffer: Vec<C> = vec![C::default(); size + 1];
+    let mut written: usize = 0;
+    napi_either(env, operation, get(buffer.as_mut_ptr(), size + 1, &mut written), ())?;
+    if written != size {
+        return Err(NapiError::new(NapiErrorType::GenericFailure,
+                                  format!("buffer size mismatch, expected {}, got {}",
+                                          size,
+                                          written)));
+    }
+    buffer.truncate(size);
+    Ok(buffer)
+}
 
 
+//     pub fn napi_coerce_to_bool(env: napi_env, value: napi_value,
+//                                result: *mut napi_value) -> napi_status;
 //     pub fn napi_coerce_to_number(env: napi_env, value: napi_value,
 //                                  result: *mut napi_value) -> napi_status;
-
-
 //     pub fn napi_coerce_to_object(env: napi_env, value: napi_value,
 //                                  result: *mut napi_value) -> napi_status;
-
-
 //     pub fn napi_coerce_to_string(env: napi_env, value: napi_value,
 //                                  result: *mut napi_value) -> napi_status;
 
+macro_rules! coerce {
+    ($name:ident, $sys_name:ident) => {
+#[track_caller]
+pub fn $name(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { $sys_name(env, value, &mut result) };
+    napi_either(env, stringify!($sys_name), status, result)
+}
+    };
+}
+
+// These follow the abstract operations `ToBoolean`, `ToNumber`, `ToObject`
+// and `ToString`, which may call into JavaScript and throw.
+coerce!(coerce_to_bool,   napi_coerce_to_bool);
+coerce!(coerce_to_number, napi_coerce_to_number);
+coerce!(coerce_to_object, napi_coerce_to_object);
+coerce!(coerce_to_string, napi_coerce_to_string);
+
 
 //     pub fn napi_get_prototype(env: napi_env, object: napi_value,
 //                               result: *mut napi_value) -> napi_status;
+#[track_caller]
+pub fn get_prototype(env: NapiEnv, object: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_prototype(env, object, &mut result) };
+    napi_either(env, "napi_get_prototype", status, result)
+}
 
 
 //     pub fn napi_get_property_names(env: napi_env, object: napi_value,
 //                                    result: *mut napi_value) -> napi_status;
+#[track_caller]
+pub fn get_property_names(env: NapiEnv, object: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_property_names(env, object, &mut result) };
+    napi_either(env, "napi_get_property_names", status, result)
+}
 
 
 //     pub fn napi_set_property(env: napi_env, object: napi_value,
 //                              key: napi_value, value: napi_value)
 //      -> napi_status;
+#[track_caller]
+pub fn set_property(env: NapiEnv, object: NapiValue, key: NapiValue, value: NapiValue) -> Result<()> {
+    let status = unsafe { napi_set_property(env, object, key, value) };
+    napi_either(env, "napi_set_property", status, ())
+}
 
 
 //     pub fn napi_has_property(env: napi_env, object: napi_value,
 //                              key: napi_value, result: *mut bool)
 //      -> napi_status;
+#[track_caller]
+pub fn has_property(env: NapiEnv, object: NapiValue, key: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_has_property(env, object, key, &mut result) };
+    napi_either(env, "napi_has_property", status, result)
+}
 
 
 //     pub fn napi_get_property(env: napi_env, object: napi_value,
 //                              key: napi_value, result: *mut napi_value)
 //      -> napi_status;
+#[track_caller]
+pub fn get_property(env: NapiEnv, object: NapiValue, key: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_property(env, object, key, &mut result) };
+    napi_either(env, "napi_get_property", status, result)
+}
 
 
 //     pub fn napi_set_named_property(env: napi_env, object: napi_value,
 //                                    utf8name: *const ::std::os::raw::c_char,
 //                                    value: napi_value) -> napi_status;
+#[track_caller]
 pub fn set_named_property(env: NapiEnv,
                           target: NapiValue,
                           name: &str,
@@ -378,33 +780,50 @@ pub fn set_named_property(env: NapiEnv,
                           -> Result<()> {
     let status =
         unsafe { napi_set_named_property(env, target, CString::new(name)?.as_ptr(), value) };
-    napi_either(env, status, ())
+    napi_either(env, "napi_set_named_property", status, ())
 }
 
 //     pub fn napi_has_named_property(env: napi_env, object: napi_value,
 //                                    utf8name: *const ::std::os::raw::c_char,
 //                                    result: *mut bool) -> napi_status;
+#[track_caller]
+pub fn has_named_property(env: NapiEnv, object: NapiValue, name: &str) -> Result<bool> {
+    let mut result = false;
+    let status =
+        unsafe { napi_has_named_property(env, object, CString::new(name)?.as_ptr(), &mut result) };
+    napi_either(env, "napi_has_named_property", status, result)
+}
+
 
+#[track_caller]
 pub fn get_named_property(env: NapiEnv, object: NapiValue, name: &str) -> Result<NapiValue> {
     let mut result: NapiValue = 0;
     let status =
         unsafe { napi_get_named_property(env, object, CString::new(name)?.as_ptr(), &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_get_named_property", status, result)
 }
 
+#[track_caller]
 pub fn set_element(env: NapiEnv, array: NapiValue, index: usize, value: NapiValue) -> Result<()> {
     let status = unsafe { napi_set_element(env, array, index as u32, value) };
-    napi_either(env, status, ())
+    napi_either(env, "napi_set_element", status, ())
 }
 
 //     pub fn napi_has_element(env: napi_env, object: napi_value, index: u32,
 //                             result: *mut bool) -> napi_status;
+#[track_caller]
+pub fn has_element(env: NapiEnv, object: NapiValue, index: usize) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_has_element(env, object, index as u32, &mut result) };
+    napi_either(env, "napi_has_element", status, result)
+}
 
 
+#[track_caller]
 pub fn get_element(env: NapiEnv, array: NapiValue, index: usize) -> Result<NapiValue> {
     let mut result: NapiValue = 0;
     let status = unsafe { napi_get_element(env, array, index as u32, &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_get_element", status, result)
 }
 
 //     pub fn napi_define_properties(env: napi_env, object: napi_value,
@@ -413,26 +832,56 @@ pub fn get_element(env: NapiEnv, array: NapiValue, index: usize) -> Result<NapiV
 //      -> napi_status;
 
 
+#[track_caller]
 pub fn is_array(env: NapiEnv, value: NapiValue) -> Result<bool> {
     let mut result: bool = false;
     let status = unsafe { napi_is_array(env, value, &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_is_array", status, result)
 }
 
+#[track_caller]
 pub fn get_array_length(env: NapiEnv, value: NapiValue) -> Result<usize> {
     let mut result: u32 = 0;
     let status = unsafe { napi_get_array_length(env, value, &mut result) };
-    napi_either(env, status, result as usize)
+    napi_either(env, "napi_get_array_length", status, result as usize)
+}
+
+#[track_caller]
+pub fn create_date(env: NapiEnv, time: f64) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_date(env, time, &mut result) };
+    napi_either(env, "napi_create_date", status, result)
+}
+
+#[track_caller]
+pub fn is_date(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result: bool = false;
+    let status = unsafe { napi_is_date(env, value, &mut result) };
+    napi_either(env, "napi_is_date", status, result)
+}
+
+#[track_caller]
+pub fn get_date_value(env: NapiEnv, value: NapiValue) -> Result<f64> {
+    let mut result: f64 = 0.0;
+    let status = unsafe { napi_get_date_value(env, value, &mut result) };
+    napi_either(env, "napi_get_date_value", status, result)
 }
 
 //     pub fn napi_strict_equals(env: napi_env, lhs: napi_value, rhs: napi_value,
 //                               result: *mut bool) -> napi_status;
+#[track_caller]
+pub fn strict_equals(env: NapiEnv, lhs: NapiValue, rhs: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_strict_equals(env, lhs, rhs, &mut result) };
+    napi_either(env, "napi_strict_equals", status, result)
+}
 
 
 //     pub fn napi_call_function(env: napi_env, recv: napi_value,
 //                               func: napi_value, argc: usize,
 //                               argv: *const napi_value,
 //                               result: *mut napi_value) -> napi_status;
+#[track_caller]
 pub fn call_function(env: NapiEnv,
                      recv: NapiValue,
                      func: NapiValue,
@@ -441,7 +890,7 @@ pub fn call_function(env: NapiEnv,
     let mut result: NapiValue = 0;
     let status =
         unsafe { napi_call_function(env, recv, func, args.len(), args.as_ptr(), &mut result) };
-    napi_either(env, status, result)
+    napi_either(env, "napi_call_function", status, result)
 }
 
 
@@ -449,11 +898,24 @@ pub fn call_function(env: NapiEnv,
 //     pub fn napi_new_instance(env: napi_env, constructor: napi_value,
 //                              argc: usize, argv: *const napi_value,
 //                              result: *mut napi_value) -> napi_status;
+#[track_caller]
+pub fn new_instance(env: NapiEnv, constructor: NapiValue, args: &[NapiValue]) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status =
+        unsafe { napi_new_instance(env, constructor, args.len(), args.as_ptr(), &mut result) };
+    napi_either(env, "napi_new_instance", status, result)
+}
 
 
 //     pub fn napi_instanceof(env: napi_env, object: napi_value,
 //                            constructor: napi_value, result: *mut bool)
 //      -> napi_status;
+#[track_caller]
+pub fn instance_of(env: NapiEnv, object: NapiValue, constructor: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_instanceof(env, object, constructor, &mut result) };
+    napi_either(env, "napi_instanceof", status, result)
+}
 
 
 //     pub fn napi_make_callback(env: napi_env, recv: napi_value,
@@ -475,20 +937,99 @@ pub fn call_function(env: NapiEnv,
 
 //     pub fn napi_define_class(env: napi_env,
 //                              utf8name: *const ::std::os::raw::c_char,
-//                              constructor: napi_callback,
+//                              length: usize, constructor: napi_callback,
 //                              data: *mut ::std::os::raw::c_void,
 //                              property_count: usize,
 //                              properties: *const napi_property_descriptor,
 //                              result: *mut napi_value) -> napi_status;
 
+/// The native function behind a method defined with `define_class`.
+pub type Method = Box<dyn for<'a> Fn(Env<'a>, JsValue<'a>, &[JsValue<'a>]) -> Result<JsValue<'a>>>;
+
+/// The value of a class property. V8 only accepts primitives as values of
+/// prototype properties, so methods are passed as native functions.
+pub enum PropertyValue {
+    Value(NapiValue),
+    Method(Method),
+}
+
+/// A property of a class defined with `define_class`, set on the prototype,
+/// or on the constructor itself if it is static.
+pub struct PropertyDescriptor {
+    pub name: String,
+    pub value: PropertyValue,
+    pub is_static: bool,
+}
+
+/// Defines a class whose constructor calls `constructor` with the new
+/// instance as `this`. Like a function created with
+/// `create_function_with_args`, the constructor owns the closure, as well as
+/// the closures of the methods.
+#[track_caller]
+pub fn define_class<F>(env: NapiEnv,
+                       utf8name: &str,
+                       constructor: F,
+                       properties: Vec<PropertyDescriptor>) -> Result<NapiValue>
+    where F: for<'a> Fn(Env<'a>, JsValue<'a>, &[JsValue<'a>]) -> Result<JsValue<'a>> + 'static
+{
+    let names = properties.iter()
+        .map(|property| CString::new(&property.name[..]))
+        .collect::<StdResult<Vec<_>, _>>()?;
+    let mut methods: Vec<Box<Method>> = Vec::new();
+    let mut descriptors: Vec<napi_property_descriptor> = Vec::new();
+    for (property, name) in properties.into_iter().zip(&names) {
+        let (method, value, data) = match property.value {
+            PropertyValue::Value(value) => (None, value, ptr::null_mut()),
+            PropertyValue::Method(method) => {
+                // the box does not move when `methods` grows
+                let method = Box::new(method);
+                let data = &*method as *const Method as *mut os::raw::c_void;
+                methods.push(method);
+                (Some(call_closure::<Method> as unsafe extern "C" fn(_, _) -> _), 0, data)
+            }
+        };
+        descriptors.push(napi_property_descriptor {
+            utf8name: name.as_ptr(),
+            name: 0,
+            method: method,
+            getter: None,
+            setter: None,
+            value: value,
+            attributes: if property.is_static {
+                napi_property_attributes::napi_static
+            } else {
+                napi_property_attributes::napi_default
+            },
+            data: data,
+        });
+    }
+    let user_data = Box::into_raw(Box::new(constructor)) as *mut os::raw::c_void;
+    let methods = Box::into_raw(Box::new(methods)) as *mut os::raw::c_void;
+    let mut napi_val: NapiValue = 0;
+    let status = unsafe {
+        napi_define_class(env,
+                          utf8name.as_ptr() as *const os::raw::c_char,
+                          utf8name.len(),
+                          Some(call_closure::<F>),
+                          user_data,
+                          descriptors.len(),
+                          descriptors.as_ptr(),
+                          &mut napi_val)
+    };
+    let status = own_closure::<F>(env, status, napi_val, user_data);
+    let status = own_closure::<Vec<Box<Method>>>(env, status, napi_val, methods);
+    napi_either(env, "napi_define_class", status, napi_val)
+}
+
 
 //     pub fn napi_wrap(env: napi_env, js_object: napi_value,
 //                      native_object: *mut ::std::os::raw::c_void,
 //                      finalize_cb: napi_finalize,
 //                      finalize_hint: *mut ::std::os::raw::c_void,
 //                      result: *mut napi_ref) -> napi_status;
+#[track_caller]
 pub fn wrap<T>(env: NapiEnv, js_object: NapiValue, native_object: Box<T>) -> Result<NapiRef> {
-    let mut result: NapiRef = unsafe { mem::uninitialized() };
+    let mut result: NapiRef = ptr::null_mut();
     let status = unsafe {
         napi_wrap(env,
                   js_object,
@@ -497,16 +1038,25 @@ pub fn wrap<T>(env: NapiEnv, js_object: NapiValue, native_object: Box<T>) -> Res
                   ptr::null_mut(),
                   &mut result)
     };
-    napi_either(env, status, result)
+    napi_either(env, "napi_wrap", status, result)
 }
 
 //     pub fn napi_unwrap(env: napi_env, js_object: napi_value,
 //                        result: *mut *mut ::std::os::raw::c_void)
 //      -> napi_status;
-pub fn unwrap<T>(env: NapiEnv, js_object: NapiValue) -> Result<Box<T>> {
+///
+/// The object keeps ownership of the native object, which is dropped when it
+/// is garbage collected.
+///
+/// # Safety
+///
+/// `js_object` has to wrap a `T`, and the reference must not outlive the
+/// handle `js_object`.
+#[track_caller]
+pub unsafe fn unwrap<'a, T>(env: NapiEnv, js_object: NapiValue) -> Result<&'a T> {
     let mut result = ptr::null_mut();
-    let status = unsafe { napi_unwrap(env, js_object, &mut result) };
-    napi_either(env, status, unsafe { Box::<T>::from_raw(result as *mut T) })
+    let status = napi_unwrap(env, js_object, &mut result);
+    napi_either(env, "napi_unwrap", status, result).map(|result| &*(result as *const T))
 }
 
 //     pub fn napi_create_external(env: napi_env,
@@ -514,6 +1064,7 @@ pub fn unwrap<T>(env: NapiEnv, js_object: NapiValue) -> Result<Box<T>> {
 //                                 finalize_cb: napi_finalize,
 //                                 finalize_hint: *mut ::std::os::raw::c_void,
 //                                 result: *mut napi_value) -> napi_status;
+#[track_caller]
 pub fn create_external<T>(env: NapiEnv, t: Box<T>) -> Result<NapiValue> {
 
     let mut result: NapiValue = 0;
@@ -524,102 +1075,202 @@ pub fn create_external<T>(env: NapiEnv, t: Box<T>) -> Result<NapiValue> {
                              ptr::null_mut(),
                              &mut result)
     };
-    napi_either(env, status, result)
+    napi_either(env, "napi_create_external", status, result)
 }
 
 unsafe extern "C" fn finalize_box<T>(_env: NapiEnv,
                                      finalize_data: *mut ::std::os::raw::c_void,
                                      _finalize_hint: *mut ::std::os::raw::c_void) {
     // move ownership into transient box in order to handle Drop, etc
-    Box::from_raw(finalize_data as *mut T);
+    if let Err(panic) = fatal::catch_panic(|| drop(Box::from_raw(finalize_data as *mut T))) {
+        panic.report();
+    }
 }
 
 //     pub fn napi_get_value_external(env: napi_env, value: napi_value,
 //                                    result: *mut *mut ::std::os::raw::c_void)
 //      -> napi_status;
-pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
+///
+/// The external keeps ownership of the data, which is dropped when it is
+/// garbage collected.
+///
+/// # Safety
+///
+/// `value` has to be an external created from a `Box<T>`, and the reference
+/// must not outlive the handle `value`.
+#[track_caller]
+pub unsafe fn get_value_external<'a, T>(env: NapiEnv, value: NapiValue) -> Result<&'a T> {
     let mut result = ptr::null_mut();
-    let status = unsafe { napi_get_value_external(env, value, &mut result) };
-    napi_either(env, status, unsafe { Box::<T>::from_raw(result as *mut T) })
+    let status = napi_get_value_external(env, value, &mut result);
+    napi_either(env, "napi_get_value_external", status, result).map(|result| &*(result as *const T))
 }
 
 
 //     pub fn napi_create_reference(env: napi_env, value: napi_value,
 //                                  initial_refcount: u32, result: *mut napi_ref)
 //      -> napi_status;
-
+#[track_caller]
+pub fn create_reference(env: NapiEnv, value: NapiValue, initial_refcount: u32) -> Result<NapiRef> {
+    let mut result: NapiRef = ptr::null_mut();
+    let status = unsafe { napi_create_reference(env, value, initial_refcount, &mut result) };
+    napi_either(env, "napi_create_reference", status, result)
+}
 
 //     pub fn napi_delete_reference(env: napi_env, ref_: napi_ref)
 //      -> napi_status;
-
+#[track_caller]
+pub fn delete_reference(env: NapiEnv, reference: NapiRef) -> Result<()> {
+    let status = unsafe { napi_delete_reference(env, reference) };
+    napi_either(env, "napi_delete_reference", status, ())
+}
 
 //     pub fn napi_reference_ref(env: napi_env, ref_: napi_ref, result: *mut u32)
 //      -> napi_status;
-
+#[track_caller]
+pub fn reference_ref(env: NapiEnv, reference: NapiRef) -> Result<u32> {
+    let mut result: u32 = 0;
+    let status = unsafe { napi_reference_ref(env, reference, &mut result) };
+    napi_either(env, "napi_reference_ref", status, result)
+}
 
 //     pub fn napi_reference_unref(env: napi_env, ref_: napi_ref,
 //                                 result: *mut u32) -> napi_status;
-
+#[track_caller]
+pub fn reference_unref(env: NapiEnv, reference: NapiRef) -> Result<u32> {
+    let mut result: u32 = 0;
+    let status = unsafe { napi_reference_unref(env, reference, &mut result) };
+    napi_either(env, "napi_reference_unref", status, result)
+}
 
 //     pub fn napi_get_reference_value(env: napi_env, ref_: napi_ref,
 //                                     result: *mut napi_value) -> napi_status;
-
+#[track_caller]
+pub fn get_reference_value(env: NapiEnv, reference: NapiRef) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_reference_value(env, reference, &mut result) };
+    napi_either(env, "napi_get_reference_value", status, result)
+}
 
 //     pub fn napi_open_handle_scope(env: napi_env,
 //                                   result: *mut napi_handle_scope)
 //      -> napi_status;
-
+#[track_caller]
+pub fn open_handle_scope(env: NapiEnv) -> Result<NapiHandleScope> {
+    let mut result: NapiHandleScope = ptr::null_mut();
+    let status = unsafe { napi_open_handle_scope(env, &mut result) };
+    napi_either(env, "napi_open_handle_scope", status, result)
+}
 
 //     pub fn napi_close_handle_scope(env: napi_env, scope: napi_handle_scope)
 //      -> napi_status;
-
+#[track_caller]
+pub fn close_handle_scope(env: NapiEnv, scope: NapiHandleScope) -> Result<()> {
+    let status = unsafe { napi_close_handle_scope(env, scope) };
+    napi_either(env, "napi_close_handle_scope", status, ())
+}
 
 //     pub fn napi_open_escapable_handle_scope(env: napi_env,
 //                                             result:
 //                                                 *mut napi_escapable_handle_scope)
 //      -> napi_status;
-
+#[track_caller]
+pub fn open_escapable_handle_scope(env: NapiEnv) -> Result<NapiEscapableHandleScope> {
+    let mut result: NapiEscapableHandleScope = ptr::null_mut();
+    let status = unsafe { napi_open_escapable_handle_scope(env, &mut result) };
+    napi_either(env, "napi_open_escapable_handle_scope", status, result)
+}
 
 //     pub fn napi_close_escapable_handle_scope(env: napi_env,
 //                                              scope:
 //                                                  napi_escapable_handle_scope)
 //      -> napi_status;
-
+#[track_caller]
+pub fn close_escapable_handle_scope(env: NapiEnv, scope: NapiEscapableHandleScope) -> Result<()> {
+    let status = unsafe { napi_close_escapable_handle_scope(env, scope) };
+    napi_either(env, "napi_close_escapable_handle_scope", status, ())
+}
 
 //     pub fn napi_escape_handle(env: napi_env,
 //                               scope: napi_escapable_handle_scope,
 //                               escapee: napi_value, result: *mut napi_value)
 //      -> napi_status;
+#[track_caller]
+pub fn escape_handle(env: NapiEnv,
+                     scope: NapiEscapableHandleScope,
+                     escapee: NapiValue)
+                     -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_escape_handle(env, scope, escapee, &mut result) };
+    napi_either(env, "napi_escape_handle", status, result)
+}
 
 
 //     pub fn napi_throw(env: napi_env, error: napi_value) -> napi_status;
+#[track_caller]
+pub fn throw(env: NapiEnv, error: NapiValue) -> Result<()> {
+    let status = unsafe { napi_throw(env, error) };
+    napi_either(env, "napi_throw", status, ())
+}
 
 
 //     pub fn napi_throw_error(env: napi_env, msg: *const ::std::os::raw::c_char)
 //      -> napi_status;
+#[track_caller]
+pub fn throw_error(env: NapiEnv, msg: &str) -> Result<()> {
+    let status = unsafe { napi_throw_error(env, ptr::null(), CString::new(msg)?.as_ptr()) };
+    napi_either(env, "napi_throw_error", status, ())
+}
 
 
 //     pub fn napi_throw_type_error(env: napi_env,
 //                                  msg: *const ::std::os::raw::c_char)
 //      -> napi_status;
+#[track_caller]
+pub fn throw_type_error(env: NapiEnv, msg: &str) -> Result<()> {
+    let status = unsafe { napi_throw_type_error(env, ptr::null(), CString::new(msg)?.as_ptr()) };
+    napi_either(env, "napi_throw_type_error", status, ())
+}
 
 
 //     pub fn napi_throw_range_error(env: napi_env,
 //                                   msg: *const ::std::os::raw::c_char)
 //      -> napi_status;
+#[track_caller]
+pub fn throw_range_error(env: NapiEnv, msg: &str) -> Result<()> {
+    let status = unsafe { napi_throw_range_error(env, ptr::null(), CString::new(msg)?.as_ptr()) };
+    napi_either(env, "napi_throw_range_error", status, ())
+}
 
 
 //     pub fn napi_is_error(env: napi_env, value: napi_value, result: *mut bool)
 //      -> napi_status;
+#[track_caller]
+pub fn is_error(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_error(env, value, &mut result) };
+    napi_either(env, "napi_is_error", status, result)
+}
 
 
 //     pub fn napi_is_exception_pending(env: napi_env, result: *mut bool)
 //      -> napi_status;
+#[track_caller]
+pub fn is_exception_pending(env: NapiEnv) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_exception_pending(env, &mut result) };
+    napi_either(env, "napi_is_exception_pending", status, result)
+}
 
 
 //     pub fn napi_get_and_clear_last_exception(env: napi_env,
 //                                              result: *mut napi_value)
 //      -> napi_status;
+#[track_caller]
+pub fn get_and_clear_last_exception(env: NapiEnv) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_and_clear_last_exception(env, &mut result) };
+    napi_either(env, "napi_get_and_clear_last_exception", status, result)
+}
 
 
 //     pub fn napi_create_buffer(env: napi_env, length: usize,
@@ -701,15 +1352,107 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 //                                   data: *mut ::std::os::raw::c_void,
 //                                   result: *mut napi_async_work)
 //      -> napi_status;
+struct AsyncWork<E, C, T> {
+    execute: Option<E>,
+    complete: Option<C>,
+    output: Option<StdResult<T, fatal::Panic>>,
+    work: napi_async_work,
+}
+
+/// Runs `execute` on the libuv thread pool and passes its result to
+/// `complete` on the JavaScript thread afterwards.
+#[track_caller]
+pub fn queue_async_work<E, C, T>(env: NapiEnv, name: &str, execute: E, complete: C) -> Result<()>
+    where E: FnOnce() -> T + Send + 'static,
+          C: FnOnce(NapiEnv, Result<T>) + 'static,
+          T: Send + 'static
+{
+    unsafe extern "C" fn execute_work<E, C, T>(_env: NapiEnv, data: *mut os::raw::c_void)
+        where E: FnOnce() -> T + Send + 'static,
+              C: FnOnce(NapiEnv, Result<T>) + 'static,
+              T: Send + 'static
+    {
+        let work = data as *mut AsyncWork<E, C, T>;
+        if let Some(execute) = (*work).execute.take() {
+            (*work).output = Some(fatal::catch_panic(execute));
+        }
+    }
 
+    unsafe extern "C" fn complete_work<E, C, T>(env: NapiEnv,
+                                                status: napi_status,
+                                                data: *mut os::raw::c_void)
+        where E: FnOnce() -> T + Send + 'static,
+              C: FnOnce(NapiEnv, Result<T>) + 'static,
+              T: Send + 'static
+    {
+        let mut work = Box::from_raw(data as *mut AsyncWork<E, C, T>);
+        let _ = delete_async_work(env, work.work);
+        let result = match (status, work.output.take()) {
+            (napi_status::napi_ok, Some(Ok(output))) => Ok(output),
+            (napi_status::napi_ok, Some(Err(panic))) => Err(panic.into_error()),
+            (napi_status::napi_ok, None) => {
+                Err(NapiError::new(NapiErrorType::GenericFailure, "async work did not run"))
+            }
+            (status, _) => {
+                Err(NapiError::new(NapiErrorType::from(status), "async work did not complete"))
+            }
+        };
+        if let Some(complete) = work.complete.take() {
+            // nothing called into JavaScript, so the panic surfaces as an
+            // uncaught exception
+            if let Err(panic) = fatal::catch_panic(|| complete(env, result)) {
+                if let Ok(exception) = panic.into_error().into_napi_value(env) {
+                    let _ = throw(env, exception);
+                }
+            }
+        }
+    }
+
+    let resource_name = create_string_utf8(env, name)?;
+    let data = Box::into_raw(Box::new(AsyncWork::<E, C, T> {
+                                          execute: Some(execute),
+                                          complete: Some(complete),
+                                          output: None,
+                                          work: ptr::null_mut(),
+                                      }));
+    let mut work: napi_async_work = ptr::null_mut();
+    let status = unsafe {
+        napi_create_async_work(env,
+                               0,
+                               resource_name,
+                               Some(execute_work::<E, C, T>),
+                               Some(complete_work::<E, C, T>),
+                               data as *mut os::raw::c_void,
+                               &mut work)
+    };
+    if let Err(err) = napi_either(env, "napi_create_async_work", status, ()) {
+        drop(unsafe { Box::from_raw(data) });
+        return Err(err);
+    }
+    unsafe { (*data).work = work };
+    if let Err(err) = queue_raw_async_work(env, work) {
+        let _ = delete_async_work(env, work);
+        drop(unsafe { Box::from_raw(data) });
+        return Err(err);
+    }
+    Ok(())
+}
 
 //     pub fn napi_delete_async_work(env: napi_env, work: napi_async_work)
 //      -> napi_status;
-
+#[track_caller]
+pub fn delete_async_work(env: NapiEnv, work: napi_async_work) -> Result<()> {
+    let status = unsafe { napi_delete_async_work(env, work) };
+    napi_either(env, "napi_delete_async_work", status, ())
+}
 
 //     pub fn napi_queue_async_work(env: napi_env, work: napi_async_work)
 //      -> napi_status;
-
+#[track_caller]
+fn queue_raw_async_work(env: NapiEnv, work: napi_async_work) -> Result<()> {
+    let status = unsafe { napi_queue_async_work(env, work) };
+    napi_either(env, "napi_queue_async_work", status, ())
+}
 
 //     pub fn napi_cancel_async_work(env: napi_env, work: napi_async_work)
 //      -> napi_status;
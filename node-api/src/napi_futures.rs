@@ -1,28 +1,54 @@
-use std::boxed::Box;
+use std::result::Result as StdResult;
 
-use napi::{NapiEnv, NapiValue, call_function};
-use napi_value::{IntoNapiValue, FromNapiValues};
-use error::Result;
+use napi_value::{IntoNapiValue, FromNapiValue, FromNapiValues};
+use napi::NapiValueType;
+use handle::JsValue;
+use function::JsFunction;
+use env::Env;
+use error::{Result, NapiError, NapiErrorType};
 
-pub struct ThenArgs<T, E> {
-    pub on_fulfilled: Box<Fn(NapiEnv, NapiValue, T) + Send>,
-    pub on_rejected: Box<Fn(NapiEnv, NapiValue, E) + Send>,
+/// The handlers passed to `then`, which are called before `then` returns.
+pub struct ThenArgs<'a> {
+    this: JsValue<'a>,
+    on_fulfilled: JsFunction<'a>,
+    on_rejected: Option<JsFunction<'a>>,
 }
 
-#[allow(unused_variables, unused_must_use)]
-impl<T, E> FromNapiValues for ThenArgs<T, E>
-    where T: IntoNapiValue
-{
-    fn from_napi_values(env: NapiEnv, this: NapiValue, values: &[NapiValue]) -> Result<Self> {
-        let fulfilled_function = values[0].clone();
+impl<'a> ThenArgs<'a> {
+    /// Calls the handler for `result`. Exceptions thrown by the handlers are
+    /// ignored.
+    pub fn settle<T, E>(&self, result: StdResult<T, E>)
+        where T: IntoNapiValue,
+              E: IntoNapiValue
+    {
+        match result {
+            Ok(value) => {
+                let _ = self.on_fulfilled.call::<_, JsValue>(self.this, (value,));
+            }
+            Err(err) => {
+                if let Some(on_rejected) = self.on_rejected {
+                    let _ = on_rejected.call::<_, JsValue>(self.this, (err,));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> FromNapiValues<'a> for ThenArgs<'a> {
+    fn from_napi_values(env: Env<'a>, this: JsValue<'a>, values: &[JsValue<'a>]) -> Result<Self> {
+        if values.is_empty() {
+            return Err(NapiError::new(NapiErrorType::InvalidArg,
+                                      "expected at least 1 argument, got 0"));
+        }
+        let on_fulfilled = JsFunction::from_napi_value(env, values[0])?;
+        let on_rejected = match values.get(1) {
+            Some(&value) if value.type_of()? == NapiValueType::Function => Some(JsFunction::from_value(value)?),
+            _ => None,
+        };
         Ok(ThenArgs {
-               on_fulfilled: Box::new(move |env, this, args| {
-                                          let napi_args = [args.into_napi_value(env).unwrap()];
-                                          call_function(env, this, fulfilled_function, &napi_args);
-                                      }),
-               on_rejected: Box::new(|_, _, _| {
-                                         println!("onrejected called");
-                                     }),
+               this: this,
+               on_fulfilled: on_fulfilled,
+               on_rejected: on_rejected,
            })
     }
 }
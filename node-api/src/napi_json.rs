@@ -0,0 +1,167 @@
+//! Structural conversions between JavaScript values and `serde_json::Value`.
+//!
+//! Values are walked directly instead of going through `JSON.stringify`.
+//! Like `JSON.stringify`, properties that are `undefined` are left out and
+//! `undefined` array elements become `null`. Functions, symbols, externals
+//! and non-finite numbers have no JSON representation and are rejected.
+
+use serde_json::{Map, Number, Value};
+
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue, MAX_SAFE_INTEGER};
+use handle::JsValue;
+use env::Env;
+use error::{NapiError, NapiErrorType, Result};
+
+/// How deep values may be nested, which also stops cyclic objects.
+const MAX_DEPTH: usize = 128;
+
+impl IntoNapiValue for Value {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        match self {
+            Value::Null => napi::get_null(env),
+            Value::Bool(value) => napi::get_boolean(env, value),
+            Value::Number(number) => number.into_napi_value(env),
+            Value::String(value) => napi::create_string_utf8(env, value),
+            Value::Array(values) => values.into_napi_value(env),
+            Value::Object(map) => map.into_napi_value(env),
+        }
+    }
+}
+
+impl IntoNapiValue for Number {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        if let Some(value) = self.as_i64() {
+            value.into_napi_value(env)
+        } else if let Some(value) = self.as_u64() {
+            value.into_napi_value(env)
+        } else {
+            napi::create_double(env, self.as_f64().unwrap_or(f64::NAN))
+        }
+    }
+}
+
+impl IntoNapiValue for Map<String, Value> {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let object = napi::create_object(env)?;
+        for (key, value) in self {
+            let value = value.into_napi_value(env)?;
+            napi::set_named_property(env, object, &key, value)?;
+        }
+        Ok(object)
+    }
+}
+
+impl<'a> FromNapiValue<'a> for Value {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Value> {
+        let mut path = String::new();
+        to_json(env.raw(), value.raw(), &mut path, 0)
+    }
+}
+
+impl<'a> FromNapiValue<'a> for Map<String, Value> {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Map<String, Value>> {
+        let (env, value) = (env.raw(), value.raw());
+        let value_type = napi::type_of(env, value)?;
+        if value_type != NapiValueType::Object || napi::is_array(env, value)? {
+            return Err(NapiError::new(NapiErrorType::ObjectExpected,
+                                      format!("expected a plain object, but found {}",
+                                              describe(env, value, value_type)?)));
+        }
+        let mut path = String::new();
+        object_to_json(env, value, &mut path, 0)
+    }
+}
+
+/// Converts `value`, found at `path` inside the value being converted.
+fn to_json(env: NapiEnv, value: NapiValue, path: &mut String, depth: usize) -> Result<Value> {
+    if depth > MAX_DEPTH {
+        return Err(json_error(path, "is nested too deeply, it may be cyclic"));
+    }
+    match napi::type_of(env, value)? {
+        NapiValueType::Undefined | NapiValueType::Null => Ok(Value::Null),
+        NapiValueType::Boolean => napi::get_value_bool(env, value).map(Value::Bool),
+        NapiValueType::Number => number_to_json(napi::get_value_double(env, value)?, path),
+        NapiValueType::String => napi::get_value_string_utf8(env, value).map(Value::String),
+        NapiValueType::Object if napi::is_array(env, value)? => {
+            let len = napi::get_array_length(env, value)?;
+            let mut values = Vec::with_capacity(len);
+            for index in 0..len {
+                let element = napi::get_element(env, value, index)?;
+                let len = path.len();
+                path.push_str(&format!("[{}]", index));
+                values.push(to_json(env, element, path, depth + 1)?);
+                path.truncate(len);
+            }
+            Ok(Value::Array(values))
+        }
+        NapiValueType::Object => object_to_json(env, value, path, depth).map(Value::Object),
+        value_type => {
+            let message = format!("is {}, which has no JSON representation",
+                                  describe(env, value, value_type)?);
+            Err(json_error(path, &message))
+        }
+    }
+}
+
+fn object_to_json(env: NapiEnv,
+                  object: NapiValue,
+                  path: &mut String,
+                  depth: usize)
+                  -> Result<Map<String, Value>> {
+    let names = napi::get_property_names(env, object)?;
+    let mut map = Map::new();
+    for index in 0..napi::get_array_length(env, names)? {
+        let name = napi::get_element(env, names, index)?;
+        let value = napi::get_property(env, object, name)?;
+        if napi::type_of(env, value)? == NapiValueType::Undefined {
+            continue;
+        }
+        let key = match napi::type_of(env, name)? {
+            NapiValueType::String => napi::get_value_string_utf8(env, name)?,
+            _ => napi::get_value_double(env, name)?.to_string(),
+        };
+        let len = path.len();
+        path.push('.');
+        path.push_str(&key);
+        let value = to_json(env, value, path, depth + 1)?;
+        path.truncate(len);
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+fn number_to_json(number: f64, path: &str) -> Result<Value> {
+    if number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER {
+        return Ok(Value::Number(Number::from(number as i64)));
+    }
+    match Number::from_f64(number) {
+        Some(number) => Ok(Value::Number(number)),
+        None => Err(json_error(path, &format!("is {}, which has no JSON representation", number))),
+    }
+}
+
+fn describe(env: NapiEnv, value: NapiValue, value_type: NapiValueType) -> Result<&'static str> {
+    Ok(match value_type {
+        NapiValueType::Undefined => "undefined",
+        NapiValueType::Null => "null",
+        NapiValueType::Boolean => "a boolean",
+        NapiValueType::Number => "a number",
+        NapiValueType::String => "a string",
+        NapiValueType::Symbol => "a symbol",
+        NapiValueType::Object if napi::is_array(env, value)? => "an array",
+        NapiValueType::Object => "an object",
+        NapiValueType::Function => "a function",
+        NapiValueType::External => "an external",
+        NapiValueType::BigInt => "a BigInt",
+    })
+}
+
+fn json_error(path: &str, message: &str) -> NapiError {
+    let location = match path.trim_start_matches('.') {
+        "" => "value",
+        location => location,
+    };
+    NapiError::new(NapiErrorType::InvalidArg, format!("`{}` {}", location, message))
+}
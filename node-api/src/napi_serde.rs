@@ -0,0 +1,751 @@
+//! Conversions between JavaScript values and types implementing serde's
+//! `Serialize` and `Deserialize`.
+//!
+//! Values are mapped the way `serde_json` maps them: structs and maps become
+//! objects, sequences and tuples become arrays, `None` and `()` become
+//! `null`, and enum variants are tagged externally, `{ "Variant": data }`.
+//! 128-bit integers become BigInts, and BigInts can be read into any integer
+//! type they fit.
+
+use std::error;
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue, MAX_SAFE_INTEGER};
+use env::Env;
+use handle::JsValue;
+use bigint::{get_bigint_i128, get_bigint_u128};
+use error::{NapiError, NapiErrorType, Result};
+
+/// Wraps a value so it is converted with serde instead of
+/// `IntoNapiValue`/`FromNapiValue`. Usable as an argument and return type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Serde<T>(pub T);
+
+impl<T> IntoNapiValue for Serde<T>
+    where T: Serialize
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        self.0.serialize(Serializer::new(env)).map_err(NapiError::from)
+    }
+}
+
+impl<'a, T> FromNapiValue<'a> for Serde<T>
+    where T: DeserializeOwned
+{
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        T::deserialize(Deserializer::new(env.raw(), value.raw())).map(Serde).map_err(NapiError::from)
+    }
+}
+
+/// The error of `Serializer` and `Deserializer`.
+#[derive(Debug, Clone)]
+pub struct SerdeError(NapiError);
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0.error_message)
+    }
+}
+
+impl error::Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError(NapiError::new(NapiErrorType::InvalidArg, msg.to_string()))
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError(NapiError::new(NapiErrorType::InvalidArg, msg.to_string()))
+    }
+}
+
+impl From<NapiError> for SerdeError {
+    fn from(err: NapiError) -> SerdeError {
+        SerdeError(err)
+    }
+}
+
+impl From<SerdeError> for NapiError {
+    fn from(err: SerdeError) -> NapiError {
+        err.0
+    }
+}
+
+type SerdeResult<T> = ::std::result::Result<T, SerdeError>;
+
+/// A serde `Serializer` producing JavaScript values.
+#[derive(Debug, Clone, Copy)]
+pub struct Serializer {
+    env: NapiEnv,
+}
+
+impl Serializer {
+    pub fn new(env: NapiEnv) -> Serializer {
+        Serializer { env: env }
+    }
+
+    /// Creates `{ variant: value }` for a variant holding data.
+    fn tagged(&self, variant: &str, value: NapiValue) -> SerdeResult<NapiValue> {
+        let object = napi::create_object(self.env)?;
+        napi::set_named_property(self.env, object, variant, value)?;
+        Ok(object)
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeObject;
+
+    fn serialize_bool(self, v: bool) -> SerdeResult<NapiValue> {
+        Ok(napi::get_boolean(self.env, v)?)
+    }
+
+    fn serialize_i8(self, v: i8) -> SerdeResult<NapiValue> {
+        self.serialize_i32(v as i32)
+    }
+
+    fn serialize_i16(self, v: i16) -> SerdeResult<NapiValue> {
+        self.serialize_i32(v as i32)
+    }
+
+    fn serialize_i32(self, v: i32) -> SerdeResult<NapiValue> {
+        Ok(napi::create_i32(self.env, v)?)
+    }
+
+    fn serialize_i64(self, v: i64) -> SerdeResult<NapiValue> {
+        Ok(v.into_napi_value(self.env)?)
+    }
+
+    fn serialize_i128(self, v: i128) -> SerdeResult<NapiValue> {
+        Ok(v.into_napi_value(self.env)?)
+    }
+
+    fn serialize_u8(self, v: u8) -> SerdeResult<NapiValue> {
+        self.serialize_u32(v as u32)
+    }
+
+    fn serialize_u16(self, v: u16) -> SerdeResult<NapiValue> {
+        self.serialize_u32(v as u32)
+    }
+
+    fn serialize_u32(self, v: u32) -> SerdeResult<NapiValue> {
+        Ok(napi::create_u32(self.env, v)?)
+    }
+
+    fn serialize_u64(self, v: u64) -> SerdeResult<NapiValue> {
+        Ok(v.into_napi_value(self.env)?)
+    }
+
+    fn serialize_u128(self, v: u128) -> SerdeResult<NapiValue> {
+        Ok(v.into_napi_value(self.env)?)
+    }
+
+    fn serialize_f32(self, v: f32) -> SerdeResult<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> SerdeResult<NapiValue> {
+        Ok(napi::create_double(self.env, v)?)
+    }
+
+    fn serialize_char(self, v: char) -> SerdeResult<NapiValue> {
+        let mut buffer = [0; 4];
+        self.serialize_str(v.encode_utf8(&mut buffer))
+    }
+
+    fn serialize_str(self, v: &str) -> SerdeResult<NapiValue> {
+        Ok(napi::create_string_utf8(self.env, v)?)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> SerdeResult<NapiValue> {
+        Ok(v.into_napi_value(self.env)?)
+    }
+
+    fn serialize_none(self) -> SerdeResult<NapiValue> {
+        Ok(napi::get_null(self.env)?)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> SerdeResult<NapiValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> SerdeResult<NapiValue> {
+        Ok(napi::get_null(self.env)?)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> SerdeResult<NapiValue> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self,
+                              _name: &'static str,
+                              _variant_index: u32,
+                              variant: &'static str)
+                              -> SerdeResult<NapiValue> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self,
+                                                       _name: &'static str,
+                                                       value: &T)
+                                                       -> SerdeResult<NapiValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self,
+                                                        _name: &'static str,
+                                                        _variant_index: u32,
+                                                        variant: &'static str,
+                                                        value: &T)
+                                                        -> SerdeResult<NapiValue> {
+        let value = value.serialize(self)?;
+        self.tagged(variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> SerdeResult<SerializeArray> {
+        SerializeArray::new(self.env, len.unwrap_or(0), None)
+    }
+
+    fn serialize_tuple(self, len: usize) -> SerdeResult<SerializeArray> {
+        SerializeArray::new(self.env, len, None)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> SerdeResult<SerializeArray> {
+        SerializeArray::new(self.env, len, None)
+    }
+
+    fn serialize_tuple_variant(self,
+                               _name: &'static str,
+                               _variant_index: u32,
+                               variant: &'static str,
+                               len: usize)
+                               -> SerdeResult<SerializeArray> {
+        SerializeArray::new(self.env, len, Some(variant))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> SerdeResult<SerializeObject> {
+        SerializeObject::new(self.env, None)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> SerdeResult<SerializeObject> {
+        SerializeObject::new(self.env, None)
+    }
+
+    fn serialize_struct_variant(self,
+                                _name: &'static str,
+                                _variant_index: u32,
+                                variant: &'static str,
+                                _len: usize)
+                                -> SerdeResult<SerializeObject> {
+        SerializeObject::new(self.env, Some(variant))
+    }
+}
+
+/// Fills an array for sequences, tuples and tuple variants.
+pub struct SerializeArray {
+    env: NapiEnv,
+    array: NapiValue,
+    index: usize,
+    variant: Option<&'static str>,
+}
+
+impl SerializeArray {
+    fn new(env: NapiEnv, len: usize, variant: Option<&'static str>) -> SerdeResult<SerializeArray> {
+        Ok(SerializeArray {
+            env: env,
+            array: napi::array_with_length(env, len)?,
+            index: 0,
+            variant: variant,
+        })
+    }
+
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        let value = value.serialize(Serializer::new(self.env))?;
+        napi::set_element(self.env, self.array, self.index, value)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> SerdeResult<NapiValue> {
+        match self.variant {
+            Some(variant) => Serializer::new(self.env).tagged(variant, self.array),
+            None => Ok(self.array),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+/// Fills an object for maps, structs and struct variants.
+pub struct SerializeObject {
+    env: NapiEnv,
+    object: NapiValue,
+    key: Option<NapiValue>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeObject {
+    fn new(env: NapiEnv, variant: Option<&'static str>) -> SerdeResult<SerializeObject> {
+        Ok(SerializeObject {
+            env: env,
+            object: napi::create_object(env)?,
+            key: None,
+            variant: variant,
+        })
+    }
+
+    fn set<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> SerdeResult<()> {
+        let value = value.serialize(Serializer::new(self.env))?;
+        napi::set_named_property(self.env, self.object, key, value)?;
+        Ok(())
+    }
+
+    fn finish(self) -> SerdeResult<NapiValue> {
+        match self.variant {
+            Some(variant) => Serializer::new(self.env).tagged(variant, self.object),
+            None => Ok(self.object),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> SerdeResult<()> {
+        let key = key.serialize(Serializer::new(self.env))?;
+        match napi::type_of(self.env, key)? {
+            NapiValueType::String | NapiValueType::Number | NapiValueType::Symbol => {
+                self.key = Some(key);
+                Ok(())
+            }
+            key_type => {
+                Err(ser::Error::custom(format_args!("map keys must be strings or numbers, found {:?}",
+                                                    key_type)))
+            }
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err(ser::Error::custom("serialize_value called before serialize_key")),
+        };
+        let value = value.serialize(Serializer::new(self.env))?;
+        napi::set_property(self.env, self.object, key, value)?;
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> SerdeResult<()> {
+        self.set(key, value)
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeObject {
+    type Ok = NapiValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> SerdeResult<()> {
+        self.set(key, value)
+    }
+
+    fn end(self) -> SerdeResult<NapiValue> {
+        self.finish()
+    }
+}
+
+/// A serde `Deserializer` reading a JavaScript value.
+///
+/// Properties that are `undefined` are treated as missing.
+#[derive(Debug, Clone, Copy)]
+pub struct Deserializer {
+    env: NapiEnv,
+    value: NapiValue,
+}
+
+impl Deserializer {
+    pub fn new(env: NapiEnv, value: NapiValue) -> Deserializer {
+        Deserializer {
+            env: env,
+            value: value,
+        }
+    }
+
+    fn unsupported(value_type: NapiValueType) -> SerdeError {
+        de::Error::custom(format_args!("cannot deserialize a value of type {:?}", value_type))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match napi::type_of(self.env, self.value)? {
+            NapiValueType::Undefined | NapiValueType::Null => visitor.visit_unit(),
+            NapiValueType::Boolean => visitor.visit_bool(napi::get_value_bool(self.env, self.value)?),
+            NapiValueType::Number => {
+                let number = napi::get_value_double(self.env, self.value)?;
+                // Integral numbers are visited as integers, so they can be
+                // read into integer types as well as floats.
+                if number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER {
+                    if number >= 0.0 {
+                        visitor.visit_u64(number as u64)
+                    } else {
+                        visitor.visit_i64(number as i64)
+                    }
+                } else {
+                    visitor.visit_f64(number)
+                }
+            }
+            NapiValueType::BigInt => {
+                let (negative, _) = napi::get_value_bigint_words(self.env, self.value)?;
+                // Visit the narrowest type, since most visitors do not
+                // accept 128-bit integers.
+                if negative {
+                    let value = get_bigint_i128(self.env, self.value)?;
+                    if value >= i64::MIN as i128 {
+                        visitor.visit_i64(value as i64)
+                    } else {
+                        visitor.visit_i128(value)
+                    }
+                } else {
+                    let value = get_bigint_u128(self.env, self.value)?;
+                    if value <= u64::MAX as u128 {
+                        visitor.visit_u64(value as u64)
+                    } else {
+                        visitor.visit_u128(value)
+                    }
+                }
+            }
+            NapiValueType::String => visitor.visit_string(napi::get_value_string_utf8(self.env, self.value)?),
+            NapiValueType::Object => {
+                if napi::is_array(self.env, self.value)? {
+                    visitor.visit_seq(ArrayAccess::new(self.env, self.value)?)
+                } else {
+                    visitor.visit_map(ObjectAccess::new(self.env, self.value)?)
+                }
+            }
+            value_type => Err(Deserializer::unsupported(value_type)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match napi::type_of(self.env, self.value)? {
+            NapiValueType::Undefined | NapiValueType::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self,
+                                                   _name: &'static str,
+                                                   visitor: V)
+                                                   -> SerdeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self,
+                                         _name: &'static str,
+                                         _variants: &'static [&'static str],
+                                         visitor: V)
+                                         -> SerdeResult<V::Value> {
+        match napi::type_of(self.env, self.value)? {
+            NapiValueType::String => {
+                let variant = napi::get_value_string_utf8(self.env, self.value)?;
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            NapiValueType::Object => {
+                let names = napi::get_property_names(self.env, self.value)?;
+                if napi::get_array_length(self.env, names)? != 1 {
+                    return Err(de::Error::custom("expected an object with a single property naming the variant"));
+                }
+                let variant = napi::get_element(self.env, names, 0)?;
+                let value = napi::get_property(self.env, self.value, variant)?;
+                visitor.visit_enum(EnumAccess {
+                    env: self.env,
+                    variant: variant,
+                    value: value,
+                })
+            }
+            value_type => {
+                Err(de::Error::custom(format_args!("expected a string or an object for an enum, found {:?}",
+                                                   value_type)))
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct ArrayAccess {
+    env: NapiEnv,
+    array: NapiValue,
+    index: usize,
+    len: usize,
+}
+
+impl ArrayAccess {
+    fn new(env: NapiEnv, array: NapiValue) -> SerdeResult<ArrayAccess> {
+        Ok(ArrayAccess {
+            env: env,
+            array: array,
+            index: 0,
+            len: napi::get_array_length(env, array)?,
+        })
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for ArrayAccess {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> SerdeResult<Option<T::Value>> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let value = napi::get_element(self.env, self.array, self.index)?;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(self.env, value)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+struct ObjectAccess {
+    env: NapiEnv,
+    object: NapiValue,
+    names: NapiValue,
+    index: usize,
+    len: usize,
+    value: Option<NapiValue>,
+}
+
+impl ObjectAccess {
+    fn new(env: NapiEnv, object: NapiValue) -> SerdeResult<ObjectAccess> {
+        let names = napi::get_property_names(env, object)?;
+        Ok(ObjectAccess {
+            env: env,
+            object: object,
+            names: names,
+            index: 0,
+            len: napi::get_array_length(env, names)?,
+            value: None,
+        })
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ObjectAccess {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> SerdeResult<Option<K::Value>> {
+        while self.index < self.len {
+            let name = napi::get_element(self.env, self.names, self.index)?;
+            self.index += 1;
+            let value = napi::get_property(self.env, self.object, name)?;
+            if napi::type_of(self.env, value)? == NapiValueType::Undefined {
+                continue;
+            }
+            self.value = Some(value);
+            return seed.deserialize(KeyDeserializer {
+                    env: self.env,
+                    key: name,
+                })
+                .map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> SerdeResult<V::Value> {
+        let value = match self.value.take() {
+            Some(value) => value,
+            None => return Err(de::Error::custom("next_value_seed called before next_key_seed")),
+        };
+        seed.deserialize(Deserializer::new(self.env, value))
+    }
+}
+
+/// Deserializes an object key. Property names are always strings, so like
+/// `serde_json` the key is parsed when a number is asked for, letting maps
+/// with numeric keys round-trip.
+struct KeyDeserializer {
+    env: NapiEnv,
+    key: NapiValue,
+}
+
+macro_rules! deserialize_numeric_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+                let key = napi::get_value_string_utf8(self.env, self.key)?;
+                match key.parse() {
+                    Ok(number) => visitor.$visit(number),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&key), &visitor)),
+                }
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        Deserializer::new(self.env, self.key).deserialize_any(visitor)
+    }
+
+    deserialize_numeric_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self,
+                                                   _name: &'static str,
+                                                   visitor: V)
+                                                   -> SerdeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self,
+                                         name: &'static str,
+                                         variants: &'static [&'static str],
+                                         visitor: V)
+                                         -> SerdeResult<V::Value> {
+        Deserializer::new(self.env, self.key).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool char str string bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumAccess {
+    env: NapiEnv,
+    variant: NapiValue,
+    value: NapiValue,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = SerdeError;
+    type Variant = Deserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> SerdeResult<(V::Value, Deserializer)> {
+        let variant = seed.deserialize(Deserializer::new(self.env, self.variant))?;
+        Ok((variant, Deserializer::new(self.env, self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> SerdeResult<()> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> SerdeResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> SerdeResult<V::Value> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self,
+                                       _fields: &'static [&'static str],
+                                       visitor: V)
+                                       -> SerdeResult<V::Value> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
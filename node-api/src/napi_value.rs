@@ -1,95 +1,175 @@
+use std::cell::RefCell;
+
 use napi;
 use futures::future;
 use futures::future::Future;
 
 use napi::NapiValueType;
 use error::{Result, NapiError, NapiErrorType};
+use js_error::JsErrorKind;
 use napi_futures;
+use env::Env;
+use handle::JsValue;
 
-pub trait FromNapiValue: Sized {
-    fn from_napi_values(napi::NapiEnv, napi::NapiValue) -> Result<Self>;
+/// Conversion of a single JavaScript value. `'a` is the scope of the value,
+/// which types holding handles, such as `JsValue<'a>`, are bound to.
+pub trait FromNapiValue<'a>: Sized {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self>;
 }
 
-pub trait FromNapiValues: Sized {
-    fn from_napi_values(napi::NapiEnv, napi::NapiValue, &[napi::NapiValue]) -> Result<Self>;
+/// Conversion of the argument list of a native function call.
+pub trait FromNapiValues<'a>: Sized {
+    fn from_napi_values(env: Env<'a>, this: JsValue<'a>, napi_values: &[JsValue<'a>]) -> Result<Self>;
 }
 
+impl<'a, T> FromNapiValues<'a> for T where T: FromNapiValue<'a> {
+    fn from_napi_values(env: Env<'a>, _: JsValue<'a>, napi_values: &[JsValue<'a>]) -> Result<T> {
+        check_napi_args_length(napi_values, 1)?;
+        T::from_napi_value(env, napi_values[0])
+    }
+}
 
-macro_rules! impl_from_napi_values {
+macro_rules! impl_from_napi_value {
     ($t:ty, $from:expr, $get_value:expr) => {
-        impl FromNapiValues for $t {
-            fn from_napi_values(env: napi::NapiEnv, _: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<$t> {
-                check_napi_args_length(env, napi_values, 1)?;
-                let value = napi_values[0];
-                check_napi_type(env, $from, value)?;
-                $get_value(env, value)
+        impl<'a> FromNapiValue<'a> for $t {
+            fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<$t> {
+                check_napi_type(env.raw(), $from, value.raw())?;
+                $get_value(env.raw(), value.raw())
             }
         }
     }
 }
 
-impl_from_napi_values!(String, NapiValueType::String,  napi::get_value_string_utf8);
-impl_from_napi_values!(i64,    NapiValueType::Number,  napi::get_value_int64);
-impl_from_napi_values!(u64,    NapiValueType::Number,  get_value_uint64);
-impl_from_napi_values!(bool,   NapiValueType::Boolean, napi::get_value_bool);
-impl_from_napi_values!(f64,    NapiValueType::Number,  napi::get_value_double);
+impl_from_napi_value!(String, NapiValueType::String,  napi::get_value_string_utf8);
+impl_from_napi_value!(bool,   NapiValueType::Boolean, napi::get_value_bool);
+impl_from_napi_value!(f64,    NapiValueType::Number,  napi::get_value_double);
+impl_from_napi_value!(f32,    NapiValueType::Number,  get_value_f32);
+
+/// The largest integer up to which all integers are exactly representable
+/// as JavaScript numbers, `Number.MAX_SAFE_INTEGER`.
+pub(crate) const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+/// Integers are read as doubles and rejected with a `RangeError` if they are
+/// not integral or do not fit, instead of being truncated or wrapped.
+macro_rules! impl_from_napi_value_for_integer {
+    ($($t:ident),+) => {
+        $(
+            impl_from_napi_value!($t, NapiValueType::Number, |env, value| -> Result<$t> {
+                let number = napi::get_value_double(env, value)?;
+                // `MAX as f64 + 1.0` is the exclusive upper bound, even for
+                // 64-bit types where `MAX as f64` already rounds up to it.
+                check_integer(number, $t::MIN as f64, $t::MAX as f64 + 1.0, stringify!($t), $t::MIN, $t::MAX)?;
+                Ok(number as $t)
+            });
+        )+
+    }
+}
+
+/// Checks that `number` is an integer in `min..end`, the range of the type
+/// `type_name`.
+pub(crate) fn check_integer<T>(number: f64, min: f64, end: f64, type_name: &str, type_min: T, type_max: T) -> Result<()>
+    where T: ::std::fmt::Display
+{
+    if number.fract() != 0.0 || !number.is_finite() {
+        Err(range_error(format!("expected an integer, but found {}", number)))
+    } else if number < min || number >= end {
+        Err(range_error(format!("{} is out of range for {} ({} to {})",
+                                number, type_name, type_min, type_max)))
+    } else {
+        Ok(())
+    }
+}
+
+impl_from_napi_value_for_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
 
+fn get_value_f32(env: napi::NapiEnv, value: napi::NapiValue) -> Result<f32> {
+    let number = napi::get_value_double(env, value)?;
+    if number.is_finite() && number.abs() > f32::MAX as f64 {
+        Err(range_error(format!("{} is out of range for f32", number)))
+    } else {
+        Ok(number as f32)
+    }
+}
 
-fn get_value_uint64(env: napi::NapiEnv, value: napi::NapiValue) -> Result<u64> {
-    napi::get_value_uint32(env, value).map(|x| x as u64)
+/// An error for a number outside the range of the type it was converted to,
+/// thrown as a JavaScript `RangeError`.
+pub(crate) fn range_error(message: String) -> NapiError {
+    let mut error = NapiError::new(NapiErrorType::InvalidArg, message);
+    error.kind = JsErrorKind::RangeError;
+    error
 }
 
-impl FromNapiValues for () {
-    fn from_napi_values(_: napi::NapiEnv, _: napi::NapiValue, _: &[napi::NapiValue]) -> Result<Self> {
+impl<'a> FromNapiValues<'a> for () {
+    fn from_napi_values(_: Env<'a>, _: JsValue<'a>, _: &[JsValue<'a>]) -> Result<Self> {
         Ok(())
     }
 }
 
-impl<T> FromNapiValues for Vec<T> where T: FromNapiValues {
-    fn from_napi_values(env: napi::NapiEnv, this: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<Self> {
-        check_napi_args_length(env, napi_values, 1)?;
-        let value = napi_values[0];
+macro_rules! impl_from_napi_values_for_tuple {
+    ($length:expr, $($name:ident => $index:expr),+) => {
+        impl<'a, $($name),+> FromNapiValues<'a> for ($($name,)+)
+            where $($name: FromNapiValue<'a>),+
+        {
+            fn from_napi_values(env: Env<'a>, _: JsValue<'a>, napi_values: &[JsValue<'a>]) -> Result<Self> {
+                check_napi_args_length(napi_values, $length)?;
+                Ok(($($name::from_napi_value(env, napi_values[$index])?,)+))
+            }
+        }
+    }
+}
+
+impl_from_napi_values_for_tuple!(1, A => 0);
+impl_from_napi_values_for_tuple!(2, A => 0, B => 1);
+impl_from_napi_values_for_tuple!(3, A => 0, B => 1, C => 2);
+impl_from_napi_values_for_tuple!(4, A => 0, B => 1, C => 2, D => 3);
+impl_from_napi_values_for_tuple!(5, A => 0, B => 1, C => 2, D => 3, E => 4);
+impl_from_napi_values_for_tuple!(6, A => 0, B => 1, C => 2, D => 3, E => 4, F => 5);
+impl_from_napi_values_for_tuple!(7, A => 0, B => 1, C => 2, D => 3, E => 4, F => 5, G => 6);
+impl_from_napi_values_for_tuple!(8, A => 0, B => 1, C => 2, D => 3, E => 4, F => 5, G => 6, H => 7);
 
-        if !napi::is_array(env, value)? {
-            Err(NapiError{error_message: "expected array".to_string(),
-                          engine_error_code: 0,
-                          error_code: NapiErrorType::InvalidArg,
-            })
+impl<'a, T> FromNapiValue<'a> for Vec<T> where T: FromNapiValue<'a> {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        if !env.is_array(value)? {
+            Err(NapiError::new(NapiErrorType::InvalidArg, "expected array"))
         } else {
-            let size = napi::get_array_length(env, value)?;
+            let size = env.get_array_length(value)?;
             let mut result = Vec::with_capacity(size);
             for i in 0..size {
-                let ival = napi::get_element(env, value, i)?;
-                result.push(FromNapiValues::from_napi_values(env, this, &[ival])?);
+                let ival = env.get_element(value, i)?;
+                result.push(T::from_napi_value(env, ival)?);
             }
             Ok(result)
         }
     }
 }
 
-fn check_napi_args_length(_env: napi::NapiEnv, napi_values: &[napi::NapiValue], expected_length: usize) -> Result<()> {
+/// `undefined` and `null` become `None`.
+impl<'a, T> FromNapiValue<'a> for Option<T> where T: FromNapiValue<'a> {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        match value.type_of()? {
+            NapiValueType::Undefined | NapiValueType::Null => Ok(None),
+            _ => T::from_napi_value(env, value).map(Some),
+        }
+    }
+}
+
+pub(crate) fn check_napi_args_length<T>(napi_values: &[T], expected_length: usize) -> Result<()> {
     let values_length = napi_values.len();
     if values_length == expected_length {
         Ok(())
     } else {
-        Err(NapiError {
-            error_message: format!("expected {} argument, got {}", expected_length, values_length),
-            engine_error_code: 0,
-            error_code: NapiErrorType::InvalidArg,
-        })
+        Err(NapiError::new(NapiErrorType::InvalidArg,
+                           format!("expected {} argument, got {}", expected_length, values_length)))
     }
 }
 
-fn check_napi_type(env: napi::NapiEnv, expected_type: NapiValueType, value: napi::NapiValue) -> Result<()> {
+pub(crate) fn check_napi_type(env: napi::NapiEnv, expected_type: NapiValueType, value: napi::NapiValue) -> Result<()> {
     let value_type = napi::type_of(env, value)?;
     if expected_type == value_type {
         Ok(())
     } else {
-        Err(NapiError {
-                error_message: format!("expected argument to be of type {:?}, but found it to be of type {:?}", expected_type, value_type),
-                engine_error_code: 0,
-            error_code: NapiErrorType::InvalidArg,
-        })
+        Err(NapiError::new(NapiErrorType::InvalidArg,
+                           format!("expected argument to be of type {:?}, but found it to be of type {:?}", expected_type, value_type)))
     }
 }
 
@@ -111,7 +191,7 @@ macro_rules! impl_into_napi_values {
 impl_into_napi_values!((), |env, _| napi::get_undefined(env));
 impl_into_napi_values!(String, napi::create_string_utf8);
 
-impl<'a> IntoNapiValue for &'a str {
+impl IntoNapiValue for &str {
     fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
         napi::create_string_utf8(env, self)
     }
@@ -119,30 +199,84 @@ impl<'a> IntoNapiValue for &'a str {
 
 impl_into_napi_values!(u8,  |env, s| napi::create_u32(env, s as u32));
 impl_into_napi_values!(u16, |env, s| napi::create_u32(env, s as u32));
-impl_into_napi_values!(u32, |env, s| napi::create_u32(env, s as u32));
+impl_into_napi_values!(u32, napi::create_u32);
+
+impl_into_napi_values!(i8,  |env, s| napi::create_i32(env, s as i32));
+impl_into_napi_values!(i16, |env, s| napi::create_i32(env, s as i32));
+impl_into_napi_values!(i32, napi::create_i32);
+
+// 64-bit integers beyond `Number.MAX_SAFE_INTEGER` would silently change
+// value as a double, so they are rejected with a `RangeError`.
+impl_into_napi_values!(i64,   |env, s| create_safe_integer(env, s as f64, s));
+impl_into_napi_values!(isize, |env, s| create_safe_integer(env, s as f64, s));
+impl_into_napi_values!(u64,   |env, s| create_safe_integer(env, s as f64, s));
+impl_into_napi_values!(usize, |env, s| create_safe_integer(env, s as f64, s));
 
-impl_into_napi_values!(i8,  |env, s| napi::create_i64(env, s as i64));
-impl_into_napi_values!(i16, |env, s| napi::create_i64(env, s as i64));
-impl_into_napi_values!(i32, |env, s| napi::create_i64(env, s as i64));
-impl_into_napi_values!(i64, |env, s| napi::create_i64(env, s as i64));
+fn create_safe_integer<T>(env: napi::NapiEnv, number: f64, value: T) -> Result<napi::NapiValue>
+    where T: ::std::fmt::Display
+{
+    if number.abs() <= MAX_SAFE_INTEGER {
+        napi::create_double(env, number)
+    } else {
+        Err(range_error(format!("{} cannot be represented exactly as a JavaScript number", value)))
+    }
+}
 
 impl_into_napi_values!(f32, |env, s| napi::create_double(env, s as f64));
 impl_into_napi_values!(f64, |env, s| napi::create_double(env, s));
 
 impl_into_napi_values!(bool,  napi::get_boolean);
 
+/// `None` becomes `null`.
+impl<T> IntoNapiValue for Option<T>
+    where T: IntoNapiValue
+{
+    fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        match self {
+            Some(value) => value.into_napi_value(env),
+            None => napi::get_null(env),
+        }
+    }
+}
+
+/// Returning an error from a native function throws it as an exception, as
+/// does failing to convert the value it returned.
+impl<T> IntoNapiValue for Result<T>
+    where T: IntoNapiValue
+{
+    fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        match self.and_then(|value| value.into_napi_value(env)) {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                let exception = err.into_napi_value(env)?;
+                napi::throw(env, exception)?;
+                napi::get_undefined(env)
+            }
+        }
+    }
+}
+
+/// Errors become JavaScript errors of their `kind`, or the original thrown
+/// value if the error was caused by an exception.
+impl IntoNapiValue for NapiError {
+    fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        match self.exception {
+            Some(ref exception) => exception.rethrown(env),
+            None => self.error_object(env),
+        }
+    }
+}
+
 
-impl<'a, T> IntoNapiValue for &'a [T]
+impl<T> IntoNapiValue for &[T]
     where T: IntoNapiValue + Clone
 {
     fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
         let array = napi::array_with_length(env, self.len())?;
 
-        let mut index: usize = 0;
-        for item in self.into_iter() {
+        for (index, item) in self.iter().enumerate() {
             let converted_item = item.clone().into_napi_value(env)?;
             napi::set_element(env, array, index, converted_item)?;
-            index = index + 1;
         }
         Ok(array)
     }
@@ -154,36 +288,72 @@ impl<T> IntoNapiValue for Vec<T>
     fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
         let array = napi::array_with_length(env, self.len())?;
 
-        let mut index: usize = 0;
-        for item in self.into_iter() {
+        for (index, item) in self.into_iter().enumerate() {
             let converted_item = item.into_napi_value(env)?;
             napi::set_element(env, array, index, converted_item)?;
-            index = index + 1;
         }
         Ok(array)
     }
 }
 
-impl<T, E> IntoNapiValue for future::BoxFuture<T, E>
+impl<T, E> IntoNapiValue for Box<dyn Future<Item = T, Error = E> + Send>
     where T: IntoNapiValue + 'static,
           E: IntoNapiValue + 'static,
 {
     fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
         let obj = napi::create_object(env)?;
-        let state = napi::create_external(env, Box::new(self))?;
-        let then = napi::create_function(env, "then", move |env, this, then_args: napi_futures::ThenArgs<T, E>| {
-            let state = napi::get_named_property(env, this, "state").unwrap();
-            let future: Box<future::BoxFuture<T,E>> = napi::get_value_external(env, state).unwrap();
+        let state = napi::create_external(env, Box::new(RefCell::new(Some(self))))?;
+        let then = napi::create_function_with_args(env, "then", move |env, this, args| {
+            let then_args = napi_futures::ThenArgs::from_napi_values(env, this, args)?;
+            let state = env.get_named_property(this, "state")?;
+            let future = unsafe { env.get_value_external::<RefCell<Option<Self>>>(state)? }
+                .borrow_mut()
+                .take()
+                .ok_or_else(|| NapiError::new(NapiErrorType::GenericFailure,
+                                              "the future has already been awaited"))?;
             future.then(move |result| {
-                match result {
-                    Ok(val) => (then_args.on_fulfilled)(env, this, val),
-                    Err(err) => (then_args.on_rejected)(env, this, err)
-                }
+                then_args.settle(result);
                 Box::new(future::result::<(),()>(Ok(())))
-            }).boxed().wait().unwrap();
+            }).wait().unwrap();
+            env.get_undefined()
         })?;
         napi::set_named_property(env, obj, "then", then)?;
         napi::set_named_property(env, obj, "state", state)?;
         Ok(obj)
     }
 }
+
+
+/// Conversion of a Rust tuple into the argument list of a JavaScript call.
+pub trait IntoNapiValues {
+    fn into_napi_values(self, env: napi::NapiEnv) -> Result<Vec<napi::NapiValue>>;
+}
+
+impl IntoNapiValues for () {
+    fn into_napi_values(self, _: napi::NapiEnv) -> Result<Vec<napi::NapiValue>> {
+        Ok(Vec::new())
+    }
+}
+
+macro_rules! impl_into_napi_values_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name),+> IntoNapiValues for ($($name,)+)
+            where $($name: IntoNapiValue),+
+        {
+            #[allow(non_snake_case)]
+            fn into_napi_values(self, env: napi::NapiEnv) -> Result<Vec<napi::NapiValue>> {
+                let ($($name,)+) = self;
+                Ok(vec![$($name.into_napi_value(env)?),+])
+            }
+        }
+    }
+}
+
+impl_into_napi_values_for_tuple!(A);
+impl_into_napi_values_for_tuple!(A, B);
+impl_into_napi_values_for_tuple!(A, B, C);
+impl_into_napi_values_for_tuple!(A, B, C, D);
+impl_into_napi_values_for_tuple!(A, B, C, D, E);
+impl_into_napi_values_for_tuple!(A, B, C, D, E, F);
+impl_into_napi_values_for_tuple!(A, B, C, D, E, F, G);
+impl_into_napi_values_for_tuple!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,82 @@
+use napi_value::{FromNapiValue, IntoNapiValue};
+use handle::{JsValue, Reference};
+use function::JsFunction;
+use env::Env;
+use error::Result;
+
+/// A Node.js style `(err, result)` callback received as an argument.
+///
+/// Put it last in the arguments of a native function to make the function
+/// usable with callback based APIs and `util.promisify`.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeCallback<'a> {
+    function: JsFunction<'a>,
+}
+
+impl<'a> NodeCallback<'a> {
+    pub fn new(function: JsFunction<'a>) -> NodeCallback<'a> {
+        NodeCallback { function: function }
+    }
+
+    pub fn function(&self) -> JsFunction<'a> {
+        self.function
+    }
+
+    /// Calls the callback with `(null, value)` on success or with a single
+    /// `Error` built from the `NapiError` on failure.
+    pub fn complete<T>(&self, result: Result<T>) -> Result<()>
+        where T: IntoNapiValue
+    {
+        complete(self.function, result)
+    }
+
+    pub fn persist(&self) -> Result<PersistentNodeCallback> {
+        Ok(PersistentNodeCallback { function: Reference::new(self.function.value())? })
+    }
+
+    /// Runs `execute` on the libuv thread pool and completes the callback with
+    /// its result, including failures of the async work itself.
+    pub fn complete_async<E, T>(&self, name: &str, execute: E) -> Result<()>
+        where E: FnOnce() -> Result<T> + Send + 'static,
+              T: IntoNapiValue + Send + 'static
+    {
+        let callback = self.persist()?;
+        let env = self.function.value().env();
+        env.queue_async_work(name, execute, move |env, result| {
+            let _ = callback.complete(env, result.and_then(|result| result));
+        })
+    }
+}
+
+impl<'a> FromNapiValue<'a> for NodeCallback<'a> {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        JsFunction::from_napi_value(env, value).map(NodeCallback::new)
+    }
+}
+
+/// A `NodeCallback` that outlives the call it was received in.
+#[derive(Debug)]
+pub struct PersistentNodeCallback {
+    function: Reference,
+}
+
+impl PersistentNodeCallback {
+    pub fn complete<T>(&self, env: Env, result: Result<T>) -> Result<()>
+        where T: IntoNapiValue
+    {
+        let function = JsFunction::from_value(self.function.get(env)?)?;
+        complete(function, result)
+    }
+}
+
+fn complete<T>(function: JsFunction, result: Result<T>) -> Result<()>
+    where T: IntoNapiValue
+{
+    let env = function.value().env();
+    let this = env.get_undefined()?;
+    let _: JsValue = match result {
+        Ok(value) => function.call(this, (env.get_null()?, value))?,
+        Err(err) => function.call(this, (err,))?,
+    };
+    Ok(())
+}
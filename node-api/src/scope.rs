@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+use napi;
+use napi::{NapiEnv, NapiHandleScope, NapiEscapableHandleScope};
+use handle::JsValue;
+use env::Env;
+use error::Result;
+
+/// Guard for an N-API handle scope.
+///
+/// Values created through `HandleScope::env` borrow the guard, so they cannot
+/// be used after the scope has been closed. The parent `Env` stays mutably
+/// borrowed while the scope is open, so it cannot create values that would
+/// land in this scope.
+#[derive(Debug)]
+pub struct HandleScope<'p, 'a: 'p> {
+    env: NapiEnv,
+    raw: NapiHandleScope,
+    _parent: PhantomData<&'p mut Env<'a>>,
+}
+
+impl<'p, 'a> HandleScope<'p, 'a> {
+    pub fn open(parent: &'p mut Env<'a>) -> Result<HandleScope<'p, 'a>> {
+        let raw = napi::open_handle_scope(parent.raw())?;
+        Ok(HandleScope {
+            env: parent.raw(),
+            raw: raw,
+            _parent: PhantomData,
+        })
+    }
+
+    pub fn env<'s>(&'s self) -> Env<'s> {
+        unsafe { Env::from_raw(self.env) }
+    }
+
+    /// Closes the scope, reporting errors that `drop` would have to ignore.
+    pub fn close(mut self) -> Result<()> {
+        let raw = self.take();
+        napi::close_handle_scope(self.env, raw)
+    }
+
+    fn take(&mut self) -> NapiHandleScope {
+        let raw = self.raw;
+        self.raw = ::std::ptr::null_mut();
+        raw
+    }
+}
+
+impl<'p, 'a> Drop for HandleScope<'p, 'a> {
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            let raw = self.take();
+            let _ = napi::close_handle_scope(self.env, raw);
+        }
+    }
+}
+
+/// Guard for an N-API escapable handle scope.
+///
+/// Like `HandleScope`, but a single value may be moved out into the
+/// enclosing scope with `escape`.
+#[derive(Debug)]
+pub struct EscapableHandleScope<'p, 'a: 'p> {
+    env: NapiEnv,
+    raw: NapiEscapableHandleScope,
+    _parent: PhantomData<&'p mut Env<'a>>,
+}
+
+impl<'p, 'a> EscapableHandleScope<'p, 'a> {
+    pub fn open(parent: &'p mut Env<'a>) -> Result<EscapableHandleScope<'p, 'a>> {
+        let raw = napi::open_escapable_handle_scope(parent.raw())?;
+        Ok(EscapableHandleScope {
+            env: parent.raw(),
+            raw: raw,
+            _parent: PhantomData,
+        })
+    }
+
+    pub fn env<'s>(&'s self) -> Env<'s> {
+        unsafe { Env::from_raw(self.env) }
+    }
+
+    /// Promotes `value` to the enclosing scope. N-API only allows this once
+    /// per scope; a second call fails.
+    pub fn escape<'s>(&'s self, value: JsValue<'s>) -> Result<JsValue<'a>> {
+        let escaped = napi::escape_handle(self.env, self.raw, value.raw())?;
+        Ok(unsafe { JsValue::from_raw(Env::from_raw(self.env), escaped) })
+    }
+
+    pub fn close(mut self) -> Result<()> {
+        let raw = self.take();
+        napi::close_escapable_handle_scope(self.env, raw)
+    }
+
+    fn take(&mut self) -> NapiEscapableHandleScope {
+        let raw = self.raw;
+        self.raw = ::std::ptr::null_mut();
+        raw
+    }
+}
+
+impl<'p, 'a> Drop for EscapableHandleScope<'p, 'a> {
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            let raw = self.take();
+            let _ = napi::close_escapable_handle_scope(self.env, raw);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::char;
+
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue, check_napi_type};
+use handle::JsValue;
+use env::Env;
+use error::Result;
+
+/// A JavaScript string as UTF-16 code units.
+///
+/// JavaScript strings may contain unpaired surrogates, which a Rust `String`
+/// cannot hold and which are replaced with U+FFFD when converting to one.
+/// `JsStringUtf16` keeps the code units as they are, so any string survives
+/// the trip from JavaScript and back unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct JsStringUtf16(pub Vec<u16>);
+
+impl JsStringUtf16 {
+    pub fn as_slice(&self) -> &[u16] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u16> {
+        self.0
+    }
+
+    /// Converts to a `String`, failing if the string contains unpaired
+    /// surrogates.
+    pub fn to_string(&self) -> Result<String> {
+        Ok(String::from_utf16(&self.0)?)
+    }
+
+    /// Converts to a `String`, replacing unpaired surrogates with U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        char::decode_utf16(self.0.iter().cloned())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+}
+
+impl<'a> From<&'a str> for JsStringUtf16 {
+    fn from(value: &'a str) -> JsStringUtf16 {
+        JsStringUtf16(value.encode_utf16().collect())
+    }
+}
+
+impl From<String> for JsStringUtf16 {
+    fn from(value: String) -> JsStringUtf16 {
+        JsStringUtf16::from(value.as_str())
+    }
+}
+
+impl From<Vec<u16>> for JsStringUtf16 {
+    fn from(value: Vec<u16>) -> JsStringUtf16 {
+        JsStringUtf16(value)
+    }
+}
+
+impl<'a> FromNapiValue<'a> for JsStringUtf16 {
+    fn from_napi_value(env: Env<'a>, value: JsValue<'a>) -> Result<JsStringUtf16> {
+        check_napi_type(env.raw(), NapiValueType::String, value.raw())?;
+        env.get_value_string_utf16(value).map(JsStringUtf16)
+    }
+}
+
+impl IntoNapiValue for JsStringUtf16 {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        napi::create_string_utf16(env, &self.0)
+    }
+}
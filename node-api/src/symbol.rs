@@ -0,0 +1,81 @@
+use napi;
+use napi::{NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue, check_napi_type};
+use handle::JsValue;
+use env::Env;
+use error::Result;
+
+/// A JavaScript symbol.
+///
+/// Symbols are unique, so properties keyed by a symbol that is not shared
+/// with JavaScript code cannot clash with other properties and do not show
+/// up in `Object.keys` or `JSON.stringify`. Use `value()` as the key with
+/// `Env::get_property` and `Env::set_property`.
+#[derive(Debug, Clone, Copy)]
+pub struct JsSymbol<'a> {
+    value: JsValue<'a>,
+}
+
+impl<'a> JsSymbol<'a> {
+    pub fn from_value(value: JsValue<'a>) -> Result<JsSymbol<'a>> {
+        check_napi_type(value.env().raw(), NapiValueType::Symbol, value.raw())?;
+        Ok(JsSymbol { value: value })
+    }
+
+    /// Creates a new unique symbol, like `Symbol(description)`.
+    pub fn new(env: Env<'a>, description: Option<&str>) -> Result<JsSymbol<'a>> {
+        let raw = napi::create_symbol(env.raw(), description)?;
+        Ok(JsSymbol { value: unsafe { env.value(raw) } })
+    }
+
+    /// Returns a well-known symbol by its property name on `Symbol`, for
+    /// example `"hasInstance"` for `Symbol.hasInstance`.
+    pub fn well_known(env: Env<'a>, name: &str) -> Result<JsSymbol<'a>> {
+        let raw = napi::get_well_known_symbol(env.raw(), name)?;
+        Ok(JsSymbol { value: unsafe { env.value(raw) } })
+    }
+
+    /// `Symbol.iterator`
+    pub fn iterator(env: Env<'a>) -> Result<JsSymbol<'a>> {
+        JsSymbol::well_known(env, "iterator")
+    }
+
+    /// `Symbol.asyncIterator`
+    pub fn async_iterator(env: Env<'a>) -> Result<JsSymbol<'a>> {
+        JsSymbol::well_known(env, "asyncIterator")
+    }
+
+    /// `Symbol.toStringTag`
+    pub fn to_string_tag(env: Env<'a>) -> Result<JsSymbol<'a>> {
+        JsSymbol::well_known(env, "toStringTag")
+    }
+
+    pub fn value(&self) -> JsValue<'a> {
+        self.value
+    }
+
+    /// Returns the description the symbol was created with, if any.
+    pub fn description(&self) -> Result<Option<String>> {
+        let env = self.value.env();
+        let description = env.get_named_property(self.value, "description")?;
+        Option::from_napi_value(env, description)
+    }
+
+    /// Whether both are the same symbol. Symbols with equal descriptions are
+    /// still distinct.
+    pub fn equals(&self, other: &JsSymbol<'a>) -> Result<bool> {
+        napi::strict_equals(self.value.env().raw(), self.value.raw(), other.value.raw())
+    }
+}
+
+impl<'a> FromNapiValue<'a> for JsSymbol<'a> {
+    fn from_napi_value(_: Env<'a>, value: JsValue<'a>) -> Result<Self> {
+        JsSymbol::from_value(value)
+    }
+}
+
+impl<'a> IntoNapiValue for JsSymbol<'a> {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value.raw())
+    }
+}
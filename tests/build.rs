@@ -0,0 +1,8 @@
+use std::env;
+
+fn main() {
+    // the N-API functions are resolved when node loads the library
+    if env::var("CARGO_CFG_TARGET_OS").map(|os| os == "macos").unwrap_or(false) {
+        println!("cargo:rustc-cdylib-link-arg=-Wl,-undefined,dynamic_lookup");
+    }
+}
@@ -1,5 +1,7 @@
 const nt = require('./node-api');
 const expect = require('chai').expect;
+const util = require('util');
+const child_process = require('child_process');
 
 describe("node-api", function() {
     describe("function returns", function() {
@@ -18,6 +20,9 @@ describe("node-api", function() {
         it("can return arrays", function() {
             expect(nt.returns_arrays()).to.deep.equal(["one", "two", "three"]);
         });
+        it("can build objects through Env", function() {
+            expect(nt.returns_env_objects()).to.deep.equal({'foo': 'hello', bar: 42});
+        });
     });
     describe("function arguments", function() {
         it("can receive objects", function() {
@@ -48,6 +53,457 @@ describe("node-api", function() {
             const arr = ["one", "two", "three"];
             expect(nt.receives_arrays(arr)).to.deep.equal(arr);
         });
+        it("can receive opaque values", function() {
+            const object = {'foo': 'hello'};
+            expect(nt.receives_values(object)).to.equal(object);
+        });
+        it("can receive any number of arguments", function() {
+            const args = Array.from({length: 20}, function(_, i) { return "arg" + i; });
+            expect(nt.receives_all_arguments.apply(null, args)).to.deep.equal(args);
+        });
+    });
+    describe("calling functions", function() {
+        it("passes typed arguments and converts results", function() {
+            expect(nt.calls_functions(function(a, b) { return a + b; })).to.equal(42);
+        });
+        it("catches exceptions thrown by the callee", function() {
+            expect(nt.catches_exceptions(function() { throw new Error("boom"); })).to.equal(true);
+        });
+        it("accepts typed callbacks", function() {
+            expect(nt.calls_callbacks(function(s) { return s + " world"; })).to.equal("hello world");
+        });
+        it("calls persisted callbacks after async work", function(done) {
+            nt.calls_callbacks_later(function(n) {
+                expect(n).to.equal(42);
+                done();
+            });
+        });
+    });
+    describe("node callbacks", function() {
+        it("passes results as (null, value)", function(done) {
+            nt.adds_with_node_callback(20, 22, function(err, sum) {
+                expect(err).to.equal(null);
+                expect(sum).to.equal(42);
+                done();
+            });
+        });
+        it("passes errors as (err)", function(done) {
+            nt.adds_with_node_callback(1, Infinity, function(err) {
+                expect(err).to.be.an.instanceof(Error);
+                expect(err.message).to.equal("expected finite numbers");
+                done();
+            });
+        });
+        it("works with util.promisify", function() {
+            return util.promisify(nt.adds_with_node_callback)(1, 2).then(function(sum) {
+                expect(sum).to.equal(3);
+            });
+        });
+    });
+    describe("#[napi] functions", function() {
+        it("are exported automatically", function() {
+            expect(nt.napi_adds(20, 22)).to.equal(42);
+        });
+        it("can be renamed", function() {
+            expect(nt.napiGreets("world")).to.equal("hello world");
+        });
+        it("throw the errors they return", function() {
+            expect(function() { nt.napiGreets(""); }).to.throw(/name must not be empty/);
+        });
+        it("report which argument failed to convert", function() {
+            expect(function() { nt.napi_adds(1, "2"); }).to.throw(/argument 1 \(`b`\)/);
+        });
+        it("only pass node_api::Env as the environment", function() {
+            expect(nt.napi_reads_config("production")).to.equal("production");
+        });
+    });
+    describe("derived conversions", function() {
+        it("rename fields and fill in defaults", function() {
+            const settings = nt.derives_settings({ displayName: "x", isEnabled: true, max_items: 5 });
+            expect(settings).to.deep.equal({ displayName: "x", isEnabled: true, retryCount: 3, max_items: 5 });
+        });
+        it("report which field failed to convert", function() {
+            expect(function() {
+                nt.derives_settings({ displayName: 1, isEnabled: true, max_items: 5 });
+            }).to.throw(/field `displayName`/);
+        });
+        it("map tuple structs to arrays and newtypes to their value", function() {
+            expect(nt.derives_points([1, 2], 3)).to.deep.equal([3, 6]);
+        });
+        it("map enums without data to strings", function() {
+            expect(nt.derives_directions("up")).to.equal("down");
+            expect(nt.derives_directions("sideways")).to.equal("sideways");
+            expect(function() { nt.derives_directions("left"); }).to.throw(/expected one of "up", "down", "sideways"/);
+        });
+        it("map numeric enums to their discriminants", function() {
+            expect(nt.derives_levels(1)).to.equal(10);
+            expect(function() { nt.derives_levels(2); }).to.throw(/expected one of 1 \(Low\), 10 \(High\)/);
+        });
+        it("map enums with data to tagged objects", function() {
+            expect(nt.derives_shapes({ type: "Circle", radius: 1 })).to.deep.equal({ type: "Rectangle", sideA: 2, sideB: 2 });
+            expect(nt.derives_shapes({ type: "Empty" })).to.deep.equal({ type: "Empty" });
+            expect(function() { nt.derives_shapes({ type: "Rectangle" }); }).to.throw(/sideA/);
+        });
+        it("put the data of tagged newtype variants in a value property", function() {
+            expect(nt.derives_shapes({ type: "Square", value: 2 })).to.deep.equal({ type: "Square", value: 4 });
+            expect(function() { nt.derives_shapes({ type: "Square" }); }).to.throw(/value/);
+            expect(function() { nt.derives_shapes({ type: "Triangle" }); }).to.throw(/unknown variant "Triangle"/);
+        });
+        it("try untagged variants in order", function() {
+            expect(nt.derives_inputs("text")).to.equal("text");
+            expect(nt.derives_inputs([1, 2])).to.deep.equal([1, 2]);
+            expect(nt.derives_inputs({ enabled: true })).to.deep.equal({ enabled: true });
+            expect(function() { nt.derives_inputs(42); }).to.throw(/did not match any variant of `Input`/);
+        });
+        it("keep fields named like the generated code apart", function() {
+            const note = { type: "Note", object: "o", tag: "t", env: 1 };
+            expect(nt.derives_notes(note)).to.deep.equal(note);
+            expect(nt.derives_notes({ object: "o", env: 2 })).to.deep.equal({ object: "o", env: 2 });
+        });
+    });
+    describe("serde conversions", function() {
+        it("round-trip nested structs, enums and maps", function() {
+            const order = nt.serde_orders({
+                id: 1,
+                items: [["apple", 0.5]],
+                status: "Open",
+                tags: { fresh: 1 },
+            });
+            expect(order).to.deep.equal({
+                id: 2,
+                items: [["apple", 0.5]],
+                note: null,
+                status: { Shipped: { carrier: "post" } },
+                tags: { fresh: 1 },
+            });
+        });
+        it("reject values of the wrong shape", function() {
+            expect(function() { nt.serde_orders({ id: "1" }); }).to.throw(/argument 0 \(`order`\)/);
+        });
+        it("parse numeric map keys", function() {
+            expect(nt.serde_numbered({ 1: "one", 2: "two" })).to.deep.equal({ 2: "one", 4: "two" });
+            expect(function() { nt.serde_numbered({ one: "one" }); }).to.throw(/invalid value: string "one"/);
+        });
+    });
+    describe("serde_json values", function() {
+        it("convert structurally", function() {
+            const value = { a: [1, 2.5, "x", null, true], b: { c: {} } };
+            expect(nt.json_values(value)).to.deep.equal({ received: value });
+        });
+        it("leave out undefined properties", function() {
+            expect(nt.json_maps({ a: 1, b: undefined })).to.deep.equal({ a: 1, count: 1 });
+        });
+        it("reject values without a JSON representation", function() {
+            expect(function() { nt.json_values({ a: [function() {}] }); }).to.throw(/`a\[0\]` is a function/);
+            expect(function() { nt.json_values(Symbol("s")); }).to.throw(/is a symbol/);
+            expect(function() { nt.json_maps([1]); }).to.throw(/expected a plain object, but found an array/);
+        });
+    });
+    describe("numbers", function() {
+        it("convert integers within range", function() {
+            expect(nt.receives_u8(255)).to.equal(255);
+            expect(nt.receives_i32(-2147483648)).to.equal(-2147483648);
+            expect(nt.receives_u64(4294967296)).to.equal(4294967296);
+        });
+        it("reject integers out of range", function() {
+            expect(function() { nt.receives_u8(256); }).to.throw(RangeError, /256 is out of range for u8/);
+            expect(function() { nt.receives_u64(-1); }).to.throw(RangeError);
+        });
+        it("reject fractions for integers", function() {
+            expect(function() { nt.receives_i32(1.5); }).to.throw(RangeError, /expected an integer/);
+        });
+        it("reject returning integers a number cannot represent", function() {
+            expect(nt.returns_u64(true)).to.equal(9007199254740991);
+            expect(function() { nt.returns_u64(false); }).to.throw(RangeError);
+        });
+    });
+    describe("BigInts", function() {
+        it("convert to and from u128", function() {
+            expect(nt.bigint_increments(2n ** 64n)).to.equal(2n ** 64n + 1n);
+            expect(function() { nt.bigint_increments(-1n); }).to.throw(RangeError);
+        });
+        it("convert losslessly to i64", function() {
+            expect(nt.bigint_to_i64(-42n)).to.equal(-42);
+            expect(function() { nt.bigint_to_i64(2n ** 63n); }).to.throw(RangeError, /out of range for i64/);
+        });
+        it("accept either a BigInt or a number with BigIntOrNumber", function() {
+            expect(nt.bigint_or_number_doubles(21)).to.equal(42);
+            expect(nt.bigint_or_number_doubles(2n ** 53n)).to.equal(2n ** 54n);
+            expect(function() { nt.bigint_or_number_doubles("1"); }).to.throw(/expected a BigInt or a number/);
+        });
+    });
+    describe("dates", function() {
+        it("convert to and from SystemTime", function() {
+            const date = new Date(Date.UTC(2018, 0, 1, 12, 30, 15, 250));
+            const later = nt.date_adds_seconds(date, 60);
+            expect(later).to.be.an.instanceof(Date);
+            expect(later.getTime()).to.equal(date.getTime() + 60000);
+            expect(nt.date_adds_seconds(new Date(-1500), 1).getTime()).to.equal(-500);
+        });
+        it("rejects invalid dates and other values", function() {
+            expect(function() { nt.date_adds_seconds(new Date("nonsense"), 1); }).to.throw(RangeError, /invalid Date/);
+            expect(function() { nt.date_adds_seconds(0, 1); }).to.throw(/expected a Date/);
+        });
+        it("creates and reads JsDate", function() {
+            expect(nt.date_from_millis(86400000).toISOString()).to.equal("1970-01-02T00:00:00.000Z");
+            expect(function() { nt.date_from_millis(9e15); }).to.throw(RangeError, /out of range for a Date/);
+            expect(nt.date_time(new Date("nonsense"))).to.be.NaN;
+        });
+        it("converts chrono types", function() {
+            expect(nt.chrono_formats(new Date(Date.UTC(2018, 0, 1, 12, 30, 15, 250)))).to.equal("2018-01-01 12:30:15.250");
+            expect(nt.chrono_parses("2018-01-01 12:30:15").toISOString()).to.equal("2018-01-01T12:30:15.000Z");
+        });
+    });
+    describe("strings", function() {
+        it("keeps NUL characters in UTF-8 strings", function() {
+            expect(nt.string_echoes("a\0b")).to.equal("a\0b");
+        });
+        it("keeps unpaired surrogates with JsStringUtf16", function() {
+            expect(nt.string_reverses_utf16("ab\ud83d")).to.equal("\ud83dba");
+            expect(function() { nt.string_reverses_utf16(1); }).to.throw(/of type String/);
+        });
+        it("converts Latin-1 strings", function() {
+            expect(nt.string_latin1_bytes("caf\u00e9")).to.deep.equal([99, 97, 102, 233]);
+            expect(nt.string_from_latin1()).to.equal("caf\u00e9");
+        });
+    });
+    describe("symbols", function() {
+        it("sets symbol-keyed properties", function() {
+            const object = nt.symbol_tags("Tagged");
+            expect(Object.prototype.toString.call(object)).to.equal("[object Tagged]");
+            expect(Object.keys(object)).to.be.empty;
+            const secret = Object.getOwnPropertySymbols(object)[1];
+            expect(secret.description).to.equal("secret");
+            expect(object[secret]).to.equal(42);
+        });
+        it("gets symbol-keyed properties", function() {
+            const key = Symbol("key");
+            expect(nt.symbol_gets({[key]: "value"}, key)).to.equal("value");
+        });
+        it("reads descriptions", function() {
+            expect(nt.symbol_describes(Symbol("described"))).to.equal("described");
+            expect(nt.symbol_describes(Symbol())).to.be.null;
+        });
+        it("compares symbols", function() {
+            expect(nt.symbol_is_iterator(Symbol.iterator)).to.be.true;
+            expect(nt.symbol_is_iterator(Symbol("iterator"))).to.be.false;
+            expect(function() { nt.symbol_is_iterator("iterator"); }).to.throw(/of type Symbol/);
+        });
+    });
+    describe("coercion", function() {
+        it("coerces numbers like Number()", function() {
+            expect(nt.coerced_adds("40", 2)).to.equal(42);
+            expect(nt.coerced_adds(true, null)).to.equal(1);
+            expect(nt.coerced_adds(10n, {valueOf: () => 5})).to.equal(15);
+            expect(nt.coerced_adds("abc", 1)).to.be.NaN;
+            expect(function() { nt.coerced_adds(Symbol(), 1); }).to.throw(TypeError);
+        });
+        it("still checks integer ranges", function() {
+            expect(nt.coerced_u8("200")).to.equal(200);
+            expect(function() { nt.coerced_u8("1.5"); }).to.throw(RangeError);
+            expect(function() { nt.receives_u8("200"); }).to.throw(/of type Number/);
+        });
+        it("coerces strings like String()", function() {
+            expect(nt.coerced_string(42)).to.equal("42");
+            expect(nt.coerced_string(null)).to.equal("null");
+            expect(nt.coerced_string(Symbol("s"))).to.equal("Symbol(s)");
+            expect(nt.coerced_string({toString: () => "custom"})).to.equal("custom");
+        });
+        it("coerces booleans like Boolean()", function() {
+            expect(nt.coerced_bool("")).to.be.false;
+            expect(nt.coerced_bool({})).to.be.true;
+        });
+    });
+    describe("classes", function() {
+        class Counter {}
+        class Lookalike {}
+        before(function() {
+            nt.class_registers(Counter);
+        });
+        it("accepts instances of the class", function() {
+            expect(nt.class_checks(new Counter())).to.be.true;
+        });
+        it("rejects other objects", function() {
+            expect(function() { nt.class_checks(new Lookalike()); }).to.throw(/expected an instance of Counter/);
+            expect(function() { nt.class_checks(1); }).to.throw(/expected an instance of Counter/);
+        });
+    });
+    describe("errors", function() {
+        it("creates errors with a code", function() {
+            const error = nt.error_creates();
+            expect(error).to.be.an.instanceof(RangeError);
+            expect(error.message).to.equal("too large");
+            expect(error.code).to.equal("ERR_OUT_OF_RANGE");
+        });
+        it("throws errors with extra properties", function() {
+            try {
+                nt.error_throws_with_code(70000);
+                expect.fail();
+            } catch (error) {
+                expect(error).to.be.an.instanceof(TypeError);
+                expect(error.code).to.equal("ERR_INVALID_ARG_VALUE");
+                expect(error.port).to.equal(70000);
+            }
+        });
+        it("keep their kind and code when they cannot be created", function() {
+            try {
+                nt.error_keeps_kind_and_code();
+                expect.fail();
+            } catch (error) {
+                expect(error).to.be.an.instanceof(RangeError);
+                expect(error.code).to.equal("ERR_OUT_OF_RANGE");
+                expect(error.message).to.match(/cannot be represented exactly/);
+            }
+        });
+        it("chains causes from Rust error sources", function() {
+            try {
+                nt.error_throws_with_cause("x");
+                expect.fail();
+            } catch (error) {
+                expect(error.message).to.equal("invalid config");
+                expect(error.cause).to.be.an.instanceof(Error);
+                expect(error.cause.message).to.equal("invalid digit found in string");
+                expect(error.cause.cause).to.be.undefined;
+            }
+        });
+    });
+    describe("exceptions", function() {
+        it("captures the name, message and stack of thrown errors", function() {
+            expect(nt.exception_details(function() { throw new TypeError("bad"); }))
+                .to.deep.equal(["TypeError", "bad", "TypeError: bad"]);
+        });
+        it("captures thrown primitives", function() {
+            expect(nt.exception_details(function() { throw "plain"; })).to.deep.equal([null, "plain", null]);
+        });
+        it("rethrows the original value", function() {
+            const error = new Error("original");
+            try {
+                nt.exception_rethrows(function() { throw error; });
+                expect.fail();
+            } catch (caught) {
+                expect(caught).to.equal(error);
+            }
+        });
+        it("rethrows exceptions that outlive their scope", function() {
+            const error = new Error("escaped");
+            try {
+                nt.exception_outlives_scope(function() { throw error; });
+                expect.fail();
+            } catch (caught) {
+                expect(caught).to.equal(error);
+            }
+        });
+        it("recreates exceptions thrown in other environments", function(done) {
+            const Worker = require("worker_threads").Worker;
+            const code = "require(" + JSON.stringify(require.resolve("./node-api")) + ")" +
+                ".exception_stores(function() { throw new TypeError('from a worker'); });";
+            new Worker(code, { eval: true }).on("exit", function() {
+                let error;
+                try {
+                    nt.exception_takes();
+                } catch (caught) {
+                    error = caught;
+                }
+                try {
+                    expect(error).to.be.an.instanceof(Error);
+                    expect(error.name).to.equal("TypeError");
+                    expect(error.message).to.equal("from a worker");
+                    expect(error.stack).to.match(/^TypeError: from a worker/);
+                    done();
+                } catch (err) {
+                    done(err);
+                }
+            });
+        });
+        it("lets Rust code recover", function() {
+            expect(nt.exception_recovers(function() { throw new Error("boom"); })).to.equal("recovered from boom");
+        });
+        it("describes the failing call", function() {
+            expect(nt.error_describes_call(function() { throw new Error("boom"); }))
+                .to.match(/^napi_call_function failed: boom \(at .*lib\.rs:\d+:\d+\)$/);
+        });
+        it("converts std errors", function() {
+            expect(nt.error_converts_std("7")).to.equal(7);
+            expect(function() { nt.error_converts_std("x"); }).to.throw("invalid digit found in string");
+        });
+        it("reports the failing status", function() {
+            expect(nt.error_reports_status({}, {})).to.equal("FunctionExpected");
+        });
+    });
+    describe("module exports", function() {
+        it("exports values and submodules", function() {
+            expect(nt.answer).to.equal(42);
+            expect(nt.nested).to.deep.equal({greeting: "hello", deeper: {flag: true}});
+        });
+        it("replaces exported objects", function() {
+            expect(nt.replaced()).to.equal("replaced");
+            expect(nt.replaced.flag).to.be.true;
+        });
+        it("exports classes", function() {
+            const tally = new nt.Tally();
+            expect(tally).to.be.an.instanceof(nt.Tally);
+            expect(nt.exports_tally(tally)).to.equal(0);
+            expect(tally.increment()).to.equal(1);
+            expect(nt.exports_tally(tally)).to.equal(1);
+            expect(nt.Tally.start).to.equal(0);
+            expect(function() { nt.exports_tally({count: 1}); }).to.throw(/expected an instance of Tally/);
+        });
+        it("reports every failed export at once", function() {
+            expect(function() { nt.exports_collect_errors(); })
+                .to.throw(/cannot register 2 exports: `big`: .*; `nested.Unregistered`: Unregistered cannot be defined/);
+        });
+    });
+    describe("native functions", function() {
+        it("can be called repeatedly", function() {
+            expect(nt.returns_strings()).to.equal("returned_string");
+            expect(nt.returns_strings()).to.equal("returned_string");
+        });
+        it("keep state between calls", function() {
+            const first = nt.counts_calls();
+            expect(nt.counts_calls()).to.equal(first + 1);
+            expect(nt.counts_calls()).to.equal(first + 2);
+        });
+        it("throw when called while running", function() {
+            expect(function() { nt.reenters(function() { nt.reenters(function() {}); }); })
+                .to.throw(/cannot be called while it is running/);
+            nt.reenters(function() {});
+        });
+        it("throw on invalid arguments", function() {
+            expect(function() { nt.receives_strings(1); }).to.throw(/of type String/);
+        });
+        it("free their closures when collected", function() {
+            const script = "const nt = require(" + JSON.stringify(__dirname + "/node-api") + ");" +
+                "(function() { nt.function_creates()(); })();" +
+                "global.gc();" +
+                "setTimeout(function() { global.gc(); setTimeout(function() { console.log(nt.function_dropped()); }, 10); }, 10);";
+            const result = child_process.spawnSync(process.execPath, ["--expose-gc", "-e", script], {encoding: "utf8"});
+            expect(result.stdout.trim()).to.equal("true");
+        });
+    });
+    describe("panics", function() {
+        it("throw from native functions with their location", function() {
+            expect(function() { nt.panic_throws(); })
+                .to.throw(/^Rust panic: deliberate panic \(at .*lib\.rs:\d+:\d+\)$/);
+            expect(nt.napi_adds(1, 2)).to.equal(3);
+        });
+        it("fail async work", function(done) {
+            nt.panic_in_async_work(function(err) {
+                expect(err).to.be.an.instanceof(Error);
+                expect(err.message).to.match(/^Rust panic: deliberate panic in async work/);
+                done();
+            });
+        });
+        it("are reported as fatal errors from finalizers", function() {
+            const script = "const nt = require(" + JSON.stringify(__dirname + "/node-api") + ");" +
+                "nt.fatal_finalizer_panics();" +
+                "setTimeout(function() { global.gc(); setTimeout(function() { global.gc(); }, 10); }, 10);";
+            const result = child_process.spawnSync(process.execPath, ["--expose-gc", "-e", script], {encoding: "utf8"});
+            expect(result.status).to.not.equal(0);
+            expect(result.stderr).to.match(/FATAL ERROR: .*lib\.rs:\d+:\d+ Rust panic: deliberate panic in a finalizer/);
+        });
+        it("are left alone outside of calls from JavaScript", function() {
+            expect(nt.fatal_leaves_panics_alone()).to.be.true;
+        });
     });
     describe("promises", function() {
         it("returns a void promise", function(done) {
@@ -56,5 +512,10 @@ describe("node-api", function() {
                 done();
             });
         });
+        it("can only be awaited once", function() {
+            const p = nt.returns_promises();
+            p.then(function() {});
+            expect(function() { p.then(function() {}); }).to.throw(/already been awaited/);
+        });
     });
 });
@@ -1,44 +1,80 @@
-#![feature(link_args)]
 #[macro_use(napi_module)]
 extern crate node_api;
 extern crate futures;
 extern crate tokio_core;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate chrono;
 
-use node_api::{NapiEnv, NapiValue, FromNapiValues, IntoNapiValue};
+use node_api::{FromNapiValue, FromNapiValues, IntoNapiValue, Env, JsValue,
+                JsFunction, JsCallback, NodeCallback, Serde, JsBigInt, BigIntOrNumber, JsDate,
+                JsStringUtf16, JsSymbol, Coerced, JsClass, ClassProperty, Instance, Reference,
+                JsError, Exports, install_panic_hook, napi};
 use node_api::error::*;
-use node_api::{
-    create_function,
-    get_named_property,
-    set_named_property,
-    create_object,
-    create_external
-};
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 use futures::future;
 use futures::Future;
 
-use tokio_core::reactor::Core;
 
 napi_module!("tests", register);
 
-#[no_mangle]
-pub unsafe extern "C" fn register(env: NapiEnv, module: NapiValue) {
+fn register(exports: &mut Exports) {
     // create_and_attach_event_loop(env, module);
 
-    register_test(env, "returns_objects",   &returns_objects);
-    register_test(env, "returns_strings",   &returns_strings);
-    register_test(env, "returns_numbers",   &returns_numbers);
-    register_test(env, "returns_booleans",  &returns_booleans);
-    register_test(env, "returns_arrays",    &returns_arrays);
+    exports.function("returns_objects",   returns_objects)
+        .function("returns_strings",   returns_strings)
+        .function("returns_numbers",   returns_numbers)
+        .function("returns_booleans",  returns_booleans)
+        .function("returns_arrays",    returns_arrays)
+        .function("returns_env_objects", returns_env_objects);
+
+    exports.function("receives_objects",  receives_objects)
+        .function("receives_strings",  receives_strings)
+        .function("receives_booleans", receives_booleans)
+        .function("receives_f64",      receives_f64)
+        .function("receives_i64",      receives_i64)
+        .function("receives_arrays",   receives_arrays)
+        .function("receives_all_arguments", receives_all_arguments);
+
+    exports.function("returns_promises",  returns_promises);
 
-    register_test(env, "receives_objects",  &receives_objects);
-    register_test(env, "receives_strings",  &receives_strings);
-    register_test(env, "receives_booleans", &receives_booleans);
-    register_test(env, "receives_f64",      &receives_f64);
-    register_test(env, "receives_i64",      &receives_i64);
-    register_test(env, "receives_arrays",   &receives_arrays);
+    let mut calls = 0;
+    exports.function_mut("counts_calls", move |_, _, ()| {
+        calls += 1;
+        calls
+    });
+    exports.function_mut("reenters", |env, _, f: Reference| -> Result<()> {
+        let f = JsFunction::from_value(f.get(env)?)?;
+        f.call::<_, JsValue>(env.get_undefined()?, ()).map(|_| ())
+    });
 
-    register_test(env, "returns_promises",  &returns_promises);
+    exports.class::<Tally>()
+        .value("answer", 42)
+        .submodule("nested", |nested| {
+            nested.value("greeting", "hello")
+                .submodule("deeper", |deeper| {
+                    deeper.value("flag", true);
+                });
+        })
+        .submodule("replaced", |replaced| {
+            let function = replaced.env().create_function("replaced", |_, _, ()| "replaced".to_string());
+            if let Ok(function) = function {
+                replaced.replace(function).value("flag", true);
+            }
+        });
 }
 
 // fn create_and_attach_event_loop(env: NapiEnv, module: NapiValue) {
@@ -46,122 +82,718 @@ pub unsafe extern "C" fn register(env: NapiEnv, module: NapiValue) {
 //    let core_js = node_api::create_external(env, core).unwrap();
 //}
 
-fn register_test<F, A, R>(env: NapiEnv, name: &str, f: F)
-    where F: Fn(NapiEnv, NapiValue, A) -> R,
-          A: FromNapiValues,
-          R: IntoNapiValue
-{
-    let test = create_function(env, name, f).unwrap();
-    let exports = create_object(env).unwrap();
-    set_named_property(env, exports, name, test).unwrap();
-}
-
 // returns objects
-fn returns_objects(_: NapiEnv, _: NapiValue, _: ()) -> Object {
+fn returns_objects(_: Env, _: JsValue, _: ()) -> Object {
     Object {
         foo: "hello".to_string(),
         bar: 42,
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
 struct Object {
     pub foo: String,
     pub bar: i64,
 }
 
-impl IntoNapiValue for Object {
-    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
-        let object = create_object(env)?;
-        let foo = self.foo.into_napi_value(env)?;
-        let bar = self.bar.into_napi_value(env)?;
-        set_named_property(env, object, "foo", foo)?;
-        set_named_property(env, object, "bar", bar)?;
-        Ok(object)
-    }
-}
-
-impl FromNapiValues for Object {
-    fn from_napi_values(env: NapiEnv,
-                        this: NapiValue,
-                        napi_values: &[NapiValue])
-                        -> Result<Object> {
-        match napi_values.len() {
-            1 => {
-                let object = napi_values[0];
-                let foo_property = get_named_property(env, object, "foo")?;
-                let bar_property = get_named_property(env, object, "bar")?;
-                Ok(Object {
-                       foo: FromNapiValues::from_napi_values(env, this, &[foo_property])?,
-                       bar: FromNapiValues::from_napi_values(env, this, &[bar_property])?,
-                   })
-            }
-            n => {
-                Err(NapiError {
-                        error_message: "expected one argument, got ".to_string() + &n.to_string(),
-                        engine_error_code: 0,
-                        error_code: NapiErrorType::InvalidArg,
-                    })
-            }
-        }
-    }
-}
-
 #[derive(Debug)]
 struct ReceivesObjectsArgs {
     pub arg0: Object,
 }
 
-impl FromNapiValues for ReceivesObjectsArgs {
-    fn from_napi_values(env: NapiEnv,
-                        this: NapiValue,
-                        napi_values: &[NapiValue])
+impl<'a> FromNapiValues<'a> for ReceivesObjectsArgs {
+    fn from_napi_values(env: Env<'a>,
+                        this: JsValue<'a>,
+                        napi_values: &[JsValue<'a>])
                         -> Result<ReceivesObjectsArgs> {
         let arg0 = Object::from_napi_values(env, this, napi_values)?;
-        Ok(ReceivesObjectsArgs { arg0: arg0 })
+        Ok(ReceivesObjectsArgs { arg0 })
+    }
+}
+
+struct AllArguments(Vec<String>);
+
+impl<'a> FromNapiValues<'a> for AllArguments {
+    fn from_napi_values(env: Env<'a>,
+                        _: JsValue<'a>,
+                        napi_values: &[JsValue<'a>])
+                        -> Result<AllArguments> {
+        let strings = napi_values.iter().map(|&value| env.from_js(value)).collect::<Result<_>>()?;
+        Ok(AllArguments(strings))
     }
 }
 
-fn returns_strings(_: NapiEnv, _: NapiValue, _: ()) -> String {
+fn receives_all_arguments(_: Env, _: JsValue, args: AllArguments) -> Vec<String> {
+    args.0
+}
+
+fn returns_strings(_: Env, _: JsValue, _: ()) -> String {
     "returned_string".to_string()
 }
 
-fn returns_numbers(_: NapiEnv, _: NapiValue, _: ()) -> f64 {
+fn returns_numbers(_: Env, _: JsValue, _: ()) -> f64 {
     42.1337
 }
 
-fn returns_booleans(_: NapiEnv, _: NapiValue, _: ()) -> bool {
+fn returns_booleans(_: Env, _: JsValue, _: ()) -> bool {
     true
 }
 
-fn returns_arrays(_: NapiEnv, _: NapiValue, _: ()) -> Vec<&'static str> {
+fn returns_arrays(_: Env, _: JsValue, _: ()) -> Vec<&'static str> {
     vec!["one", "two", "three"]
 }
 
-fn receives_objects(_: NapiEnv, _: NapiValue, args: ReceivesObjectsArgs) -> Object {
+fn returns_env_objects(env: Env, _: JsValue, _: ()) -> Result<Object> {
+    let object = env.create_object()?;
+    env.set_named_property(object, "foo", env.create_string_utf8("hello")?)?;
+    env.set_named_property(object, "bar", env.create_i64(42)?)?;
+    env.from_js(object)
+}
+
+fn receives_objects(_: Env, _: JsValue, args: ReceivesObjectsArgs) -> Object {
     args.arg0
 }
 
-fn receives_strings(_: NapiEnv, _: NapiValue, arg: String) -> String {
+fn receives_strings(_: Env, _: JsValue, arg: String) -> String {
     arg
 }
 
-fn receives_booleans(_: NapiEnv, _: NapiValue, arg: bool) -> bool {
+fn receives_booleans(_: Env, _: JsValue, arg: bool) -> bool {
     arg
 }
 
-fn receives_f64(_: NapiEnv, _: NapiValue, arg: f64) -> f64 {
+fn receives_f64(_: Env, _: JsValue, arg: f64) -> f64 {
     arg
 }
 
-fn receives_i64(_: NapiEnv, _: NapiValue, arg: i64) -> i64 {
+fn receives_i64(_: Env, _: JsValue, arg: i64) -> i64 {
     arg
 }
 
-fn receives_arrays(_: NapiEnv, _: NapiValue, arg: Vec<String>) -> Vec<String> {
+fn receives_arrays(_: Env, _: JsValue, arg: Vec<String>) -> Vec<String> {
     arg
 }
 
-fn returns_promises(_: NapiEnv, _: NapiValue, _arg: ()) -> futures::BoxFuture<(), ()> {
-    future::ok(()).boxed()
+#[napi]
+fn receives_values<'a>(mut env: Env<'a>, arg: JsValue<'a>) -> Result<JsValue<'a>> {
+    env.escapable_handle_scope(|_| Ok(arg))
+}
+
+#[napi]
+fn calls_functions(f: JsFunction) -> f64 {
+    let this = f.value().env().get_undefined().unwrap();
+    f.call(this, (20.5, 21.5)).unwrap()
+}
+
+#[napi]
+fn catches_exceptions(f: JsFunction) -> bool {
+    let this = f.value().env().get_undefined().unwrap();
+    match f.call::<_, JsValue>(this, ()) {
+        Err(err) => err.error_code == NapiErrorType::PendingException && err.exception.is_some(),
+        Ok(_) => false,
+    }
+}
+
+#[napi]
+fn calls_callbacks(cb: JsCallback<(String,), String>) -> String {
+    cb.call(("hello".to_string(),)).unwrap()
+}
+
+#[napi]
+fn calls_callbacks_later(env: Env, cb: JsCallback<(f64,), Option<f64>>) {
+    let cb = cb.persist().unwrap();
+    env.queue_async_work("calls_callbacks_later", || 42.0, move |env, result| {
+        cb.call(env, (result.unwrap(),)).unwrap();
+    }).unwrap();
+}
+
+#[napi]
+fn adds_with_node_callback(a: f64, b: f64, callback: NodeCallback) {
+    callback.complete_async("adds_with_node_callback", move || if a.is_finite() && b.is_finite() {
+        Ok(a + b)
+    } else {
+        Err(NapiError::new(NapiErrorType::InvalidArg, "expected finite numbers"))
+    }).unwrap();
+}
+
+#[napi]
+fn napi_adds(a: f64, b: f64) -> f64 {
+    a + b
+}
+
+#[napi(js_name = "napiGreets")]
+fn napi_greets(name: String) -> Result<String> {
+    if name.is_empty() {
+        return Err(NapiError::new(NapiErrorType::InvalidArg, "name must not be empty"));
+    }
+    Ok(format!("hello {}", name))
+}
+
+mod config {
+    /// Not `node_api::Env`, so `#[napi]` converts it from an argument.
+    pub type Env = String;
+}
+
+#[napi]
+fn napi_reads_config(env: node_api::Env, name: config::Env) -> Result<String> {
+    env.from_js(env.create_string_utf8(&name)?)
+}
+
+#[derive(Debug, Default, IntoNapiValue, FromNapiValue)]
+struct Limits {
+    max_items: i64,
+}
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+#[napi(rename_all = "camelCase")]
+struct Settings {
+    display_name: String,
+    #[napi(rename = "isEnabled")]
+    enabled: bool,
+    #[napi(default = "default_retries")]
+    retry_count: i64,
+    #[napi(skip)]
+    cache: Vec<String>,
+    #[napi(flatten)]
+    limits: Limits,
+}
+
+fn default_retries() -> i64 {
+    3
+}
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+struct Point(f64, f64);
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+struct Meters(f64);
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+#[napi(rename_all = "lowercase")]
+enum Direction {
+    Up,
+    Down,
+    #[napi(rename = "sideways")]
+    Sideways,
+}
+
+#[derive(Debug, Clone, Copy, IntoNapiValue, FromNapiValue)]
+#[napi(numeric)]
+enum Level {
+    Low = 1,
+    High = 10,
+}
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+enum Shape {
+    Circle { radius: f64 },
+    #[napi(rename_all = "camelCase")]
+    Rectangle { side_a: f64, side_b: f64 },
+    Square(f64),
+    Empty,
+}
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+#[napi(untagged)]
+enum Input {
+    Text(String),
+    Pair(f64, f64),
+    Flag { enabled: bool },
+}
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+enum Note {
+    Note { object: String, tag: String, env: f64 },
+}
+
+#[derive(Debug, IntoNapiValue, FromNapiValue)]
+#[napi(untagged)]
+enum LooseNote {
+    Tagged(Note),
+    Loose { object: String, env: f64 },
+}
+
+#[napi]
+fn derives_settings(settings: Settings) -> Settings {
+    assert!(settings.cache.is_empty());
+    settings
+}
+
+#[napi]
+fn derives_points(point: Point, scale: Meters) -> Point {
+    Point(point.0 * scale.0, point.1 * scale.0)
+}
+
+fn returns_promises(_: Env, _: JsValue, _arg: ()) -> Box<dyn Future<Item = (), Error = ()> + Send> {
+    Box::new(future::ok(()))
+}
+
+#[napi]
+fn derives_directions(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Down,
+        Direction::Down => Direction::Up,
+        Direction::Sideways => Direction::Sideways,
+    }
+}
+
+#[napi]
+fn derives_levels(level: Level) -> Level {
+    match level {
+        Level::Low => Level::High,
+        Level::High => Level::Low,
+    }
+}
+
+#[napi]
+fn derives_shapes(shape: Shape) -> Shape {
+    match shape {
+        Shape::Circle { radius } => Shape::Rectangle { side_a: radius * 2.0, side_b: radius * 2.0 },
+        Shape::Square(side) => Shape::Square(side * 2.0),
+        shape => shape,
+    }
+}
+
+#[napi]
+fn derives_inputs(input: Input) -> Input {
+    input
+}
+
+#[napi]
+fn derives_notes(note: LooseNote) -> LooseNote {
+    note
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Order {
+    id: u32,
+    items: Vec<OrderItem>,
+    note: Option<String>,
+    status: OrderStatus,
+    tags: std::collections::BTreeMap<String, i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OrderItem(String, f64);
+
+#[derive(Debug, Serialize, Deserialize)]
+enum OrderStatus {
+    Open,
+    Shipped { carrier: String },
+}
+
+#[napi]
+fn serde_orders(order: Serde<Order>) -> Serde<Order> {
+    let mut order = order.0;
+    order.id += 1;
+    if let OrderStatus::Open = order.status {
+        order.status = OrderStatus::Shipped { carrier: "post".to_string() };
+    }
+    Serde(order)
+}
+
+#[napi]
+fn serde_numbered(names: Serde<std::collections::HashMap<u32, String>>)
+                  -> Serde<std::collections::HashMap<u32, String>> {
+    Serde(names.0.into_iter().map(|(number, name)| (number * 2, name)).collect())
+}
+
+#[napi]
+fn json_values(value: serde_json::Value) -> serde_json::Value {
+    json!({ "received": value })
+}
+
+#[napi]
+fn json_maps(mut map: serde_json::Map<String, serde_json::Value>) -> serde_json::Map<String, serde_json::Value> {
+    map.insert("count".to_string(), json!(map.len()));
+    map
+}
+
+#[napi]
+fn receives_u8(value: u8) -> u8 {
+    value
+}
+
+#[napi]
+fn receives_i32(value: i32) -> i32 {
+    value
+}
+
+#[napi]
+fn receives_u64(value: u64) -> u64 {
+    value
+}
+
+#[napi]
+fn returns_u64(safe: bool) -> u64 {
+    if safe { 9007199254740991 } else { 9007199254740993 }
+}
+
+#[napi]
+fn bigint_increments(value: u128) -> u128 {
+    value + 1
+}
+
+#[napi]
+fn bigint_to_i64(value: JsBigInt) -> Result<BigIntOrNumber<i64>> {
+    value.to_i64().map(BigIntOrNumber)
+}
+
+#[napi]
+fn bigint_or_number_doubles(value: BigIntOrNumber<u64>) -> BigIntOrNumber<u64> {
+    BigIntOrNumber(value.0 * 2)
+}
+
+#[napi]
+fn date_adds_seconds(time: SystemTime, seconds: u64) -> SystemTime {
+    time + Duration::from_secs(seconds)
+}
+
+#[napi]
+fn date_from_millis(env: Env, time: f64) -> Result<JsDate> {
+    JsDate::new(env, time)
+}
+
+#[napi]
+fn date_time(date: JsDate) -> Result<f64> {
+    date.time()
+}
+
+#[napi]
+fn chrono_formats(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+#[napi]
+fn chrono_parses(time: String) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S")
+        .map_err(|err| NapiError::new(NapiErrorType::InvalidArg, err.to_string()))
+}
+
+#[napi]
+fn string_echoes(value: String) -> String {
+    value
+}
+
+#[napi]
+fn string_reverses_utf16(value: JsStringUtf16) -> JsStringUtf16 {
+    JsStringUtf16(value.0.into_iter().rev().collect())
+}
+
+#[napi]
+fn string_latin1_bytes(env: Env, value: JsValue) -> Result<Vec<u32>> {
+    let bytes = env.get_value_string_latin1(value)?;
+    Ok(bytes.into_iter().map(u32::from).collect())
+}
+
+#[napi]
+fn string_from_latin1(env: Env) -> Result<JsValue> {
+    env.create_string_latin1(b"caf\xe9")
+}
+
+#[napi]
+fn symbol_tags(env: Env, tag: String) -> Result<JsValue> {
+    let object = env.create_object()?;
+    let to_string_tag = JsSymbol::to_string_tag(env)?;
+    env.set_property(object, to_string_tag.value(), env.to_js(tag)?)?;
+    let secret = JsSymbol::new(env, Some("secret"))?;
+    env.set_property(object, secret.value(), env.to_js(42)?)?;
+    Ok(object)
+}
+
+#[napi]
+fn symbol_gets(env: Env, object: JsValue, key: JsSymbol) -> Result<String> {
+    env.from_js(env.get_property(object, key.value())?)
+}
+
+#[napi]
+fn symbol_describes(symbol: JsSymbol) -> Result<Option<String>> {
+    symbol.description()
+}
+
+#[napi]
+fn symbol_is_iterator(env: Env, symbol: JsSymbol) -> Result<bool> {
+    symbol.equals(&JsSymbol::iterator(env)?)
+}
+
+#[napi]
+fn coerced_adds(a: Coerced<f64>, b: Coerced<f64>) -> f64 {
+    a.0 + b.0
+}
+
+#[napi]
+fn coerced_u8(value: Coerced<u8>) -> u8 {
+    value.0
+}
+
+#[napi]
+fn coerced_string(value: Coerced<String>) -> String {
+    value.0
+}
+
+#[napi]
+fn coerced_bool(value: Coerced<bool>) -> bool {
+    value.0
+}
+
+struct Counter;
+
+thread_local! {
+    static COUNTER: RefCell<Option<Reference>> = const { RefCell::new(None) };
+}
+
+impl JsClass for Counter {
+    const NAME: &'static str = "Counter";
+
+    fn constructor<'a>(env: Env<'a>) -> Result<JsValue<'a>> {
+        COUNTER.with(|counter| match *counter.borrow() {
+            Some(ref constructor) => constructor.get(env),
+            None => Err(NapiError::new(NapiErrorType::GenericFailure, "Counter is not registered".to_string())),
+        })
+    }
+}
+
+#[napi]
+fn class_registers(constructor: JsFunction) -> Result<()> {
+    let reference = Reference::new(constructor.value())?;
+    COUNTER.with(|counter| *counter.borrow_mut() = Some(reference));
+    Ok(())
+}
+
+#[napi]
+fn class_checks(counter: Instance<Counter>) -> Result<bool> {
+    let value = counter.value();
+    let prototype = value.env().get_prototype(value)?;
+    let constructor = Counter::constructor(value.env())?;
+    let expected = value.env().get_named_property(constructor, "prototype")?;
+    prototype.strict_equals(expected)
+}
+
+#[napi]
+fn error_creates(env: Env) -> Result<JsValue> {
+    env.create_range_error(Some("ERR_OUT_OF_RANGE"), "too large")
+}
+
+#[napi]
+fn error_throws_with_code(env: Env, port: u32) -> Result<()> {
+    Err(JsError::type_error("invalid port")
+        .code("ERR_INVALID_ARG_VALUE")
+        .property("port", port)
+        .into_napi_error(env))
+}
+
+#[napi]
+fn error_keeps_kind_and_code(env: Env) -> Result<()> {
+    let err = JsError::range_error("too large")
+        .code("ERR_OUT_OF_RANGE")
+        .property("limit", u64::MAX)
+        .into_napi_error(env);
+    assert!(err.exception.is_none());
+    Err(err)
+}
+
+#[derive(Debug)]
+struct ConfigError(ParseIntError);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid config")
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[napi]
+fn error_throws_with_cause(env: Env, value: String) -> Result<u32> {
+    value.parse().map_err(|err| JsError::from_error(&ConfigError(err)).into_napi_error(env))
+}
+
+#[napi]
+fn exception_details(f: JsFunction) -> Result<Vec<Option<String>>> {
+    let this = f.value().env().get_undefined()?;
+    match f.call::<_, JsValue>(this, ()) {
+        Err(NapiError { exception: Some(exception), .. }) => {
+            let stack = exception.stack.and_then(|stack| stack.lines().next().map(String::from));
+            Ok(vec![exception.name, exception.message, stack])
+        }
+        Err(err) => Err(err),
+        Ok(_) => Ok(vec![]),
+    }
+}
+
+#[napi]
+fn exception_rethrows(f: JsFunction) -> Result<f64> {
+    let this = f.value().env().get_undefined()?;
+    f.call(this, ())
+}
+
+#[napi]
+fn exception_outlives_scope(f: JsFunction) -> Result<()> {
+    let mut env = f.value().env();
+    let err = env.handle_scope(|scope| {
+        let env = scope.env();
+        let this = env.get_undefined()?;
+        env.call_function(this, f.value(), &[]).map(|_| ())
+    }).unwrap_err();
+    Err(thread::spawn(move || err).join().unwrap())
+}
+
+static STORED_EXCEPTION: Mutex<Option<NapiError>> = Mutex::new(None);
+
+#[napi]
+fn exception_stores(f: JsFunction) {
+    let this = f.value().env().get_undefined().unwrap();
+    let err = f.call::<_, JsValue>(this, ()).unwrap_err();
+    *STORED_EXCEPTION.lock().unwrap() = Some(err);
+}
+
+#[napi]
+fn exception_takes() -> Result<()> {
+    Err(STORED_EXCEPTION.lock().unwrap().take().unwrap())
+}
+
+#[napi]
+fn exception_recovers(f: JsFunction) -> Result<String> {
+    let this = f.value().env().get_undefined()?;
+    match f.call::<_, String>(this, ()) {
+        Err(NapiError { error_code: NapiErrorType::PendingException, error_message, .. }) => {
+            Ok(format!("recovered from {}", error_message))
+        }
+        result => result,
+    }
+}
+
+fn call_boxed<'a>(f: JsFunction<'a>) -> ::std::result::Result<JsValue<'a>, Box<dyn Error>> {
+    let env = f.value().env();
+    let this = env.get_undefined()?;
+    Ok(env.call_function(this, f.value(), &[])?)
+}
+
+#[napi]
+fn error_describes_call(f: JsFunction) -> Result<String> {
+    match call_boxed(f) {
+        Err(err) => Ok(err.to_string()),
+        Ok(_) => Ok(String::new()),
+    }
+}
+
+#[napi]
+fn error_converts_std(value: String) -> Result<u8> {
+    Ok(value.parse()?)
+}
+
+#[napi]
+fn error_reports_status(env: Env, object: JsValue, constructor: JsValue) -> Result<String> {
+    match env.instance_of(object, constructor) {
+        Err(err) => Ok(format!("{:?}", err.error_code)),
+        Ok(_) => Ok(String::new()),
+    }
+}
+
+#[napi]
+fn panic_throws() {
+    install_panic_hook();
+    panic!("deliberate panic");
+}
+
+#[napi]
+fn panic_in_async_work(callback: NodeCallback) -> Result<()> {
+    install_panic_hook();
+    callback.complete_async("panic_in_async_work", || -> Result<f64> { panic!("deliberate panic in async work") })
+}
+
+struct PanicsOnDrop;
+
+impl Drop for PanicsOnDrop {
+    fn drop(&mut self) {
+        panic!("deliberate panic in a finalizer");
+    }
+}
+
+#[napi]
+fn fatal_finalizer_panics(env: Env) -> Result<JsValue> {
+    install_panic_hook();
+    env.create_external(Box::new(PanicsOnDrop))
+}
+
+#[napi]
+fn fatal_leaves_panics_alone() -> bool {
+    install_panic_hook();
+    let caught = ::std::panic::catch_unwind(|| panic!("recovered panic")).is_err();
+    let joined = thread::spawn(|| panic!("panic on another thread")).join().is_err();
+    caught && joined
+}
+
+struct Tally;
+
+impl JsClass for Tally {
+    const NAME: &'static str = "Tally";
+
+    fn construct<'a>(env: Env<'a>, this: JsValue<'a>, _: &[JsValue<'a>]) -> Result<()> {
+        env.set_named_property(this, "count", env.create_u32(0)?)
+    }
+
+    fn properties<'a>(env: Env<'a>) -> Result<Vec<ClassProperty<'a>>> {
+        let increment = ClassProperty::method("increment", |env, this, ()| -> Result<u32> {
+            let count = env.from_js::<u32>(env.get_named_property(this, "count")?)? + 1;
+            env.set_named_property(this, "count", env.create_u32(count)?)?;
+            Ok(count)
+        });
+        Ok(vec![increment, ClassProperty::static_value("start", env.create_u32(0)?)])
+    }
+}
+
+#[napi]
+fn exports_tally(tally: Instance<Tally>) -> Result<u32> {
+    let value = tally.value();
+    value.env().from_js(value.env().get_named_property(value, "count")?)
+}
+
+struct Unregistered;
+
+impl JsClass for Unregistered {
+    const NAME: &'static str = "Unregistered";
+
+    fn properties<'a>(_: Env<'a>) -> Result<Vec<ClassProperty<'a>>> {
+        Err(NapiError::new(NapiErrorType::GenericFailure, "Unregistered cannot be defined"))
+    }
+}
+
+#[napi]
+fn exports_collect_errors(env: Env) -> Result<()> {
+    let object = env.create_object()?;
+    let mut exports = Exports::new(env, object);
+    exports.value("fine", 1)
+        .value("big", u64::MAX)
+        .submodule("nested", |nested| {
+            nested.class::<Unregistered>();
+        });
+    exports.finish()
+}
+
+static FUNCTION_DROPPED: AtomicBool = AtomicBool::new(false);
+
+struct DropFlag;
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        FUNCTION_DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[napi]
+fn function_creates(env: Env) -> Result<JsValue> {
+    let flag = DropFlag;
+    env.create_function("flagged", move |_, _, ()| {
+        let _ = &flag;
+    })
+}
+
+#[napi]
+fn function_dropped() -> bool {
+    FUNCTION_DROPPED.load(Ordering::SeqCst)
 }